@@ -0,0 +1,175 @@
+use crate::compress::{self, MAX_BROTLI_QUALITY, MAX_GZIP_LEVEL};
+use crate::config::{ProjectConfig, SizeBudget};
+use crate::size::SizeReport;
+use crate::{PluginError, PluginResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BudgetViolation {
+    /// `size`, `gzip`, `brotli` or `section:<name>`.
+    pub metric: String,
+    pub limit: u64,
+    pub actual: u64,
+}
+
+impl fmt::Display for BudgetViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is {} bytes, over the {} byte limit by {}",
+            self.metric,
+            self.actual,
+            self.limit,
+            self.actual - self.limit
+        )
+    }
+}
+
+pub(crate) fn describe(violations: &[BudgetViolation]) -> String {
+    violations
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Checks every artifact against the budgets whose pattern matches its file
+/// name, failing on the first artifact that exceeds one.
+///
+/// Compressed sizes are measured at maximum gzip and brotli settings, which is
+/// what static hosts and CDNs typically serve.
+pub fn check_budgets(config: &ProjectConfig, artifacts: &[PathBuf]) -> PluginResult<()> {
+    if config.budgets.is_empty() {
+        return Ok(());
+    }
+
+    for artifact in artifacts {
+        let file_name = artifact
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let budgets: Vec<&SizeBudget> = config
+            .budgets
+            .iter()
+            .filter(|(pattern, _)| matches_pattern(pattern, &file_name))
+            .map(|(_, budget)| budget)
+            .collect();
+        if budgets.is_empty() {
+            continue;
+        }
+
+        let bytes = fs::read(artifact).map_err(PluginError::Io)?;
+        let mut violations = Vec::new();
+        for budget in budgets {
+            violations.extend(measure(&bytes, budget)?);
+        }
+
+        if !violations.is_empty() {
+            return Err(PluginError::SizeBudgetExceeded {
+                artifact: artifact.display().to_string(),
+                violations,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+pub fn measure(bytes: &[u8], budget: &SizeBudget) -> PluginResult<Vec<BudgetViolation>> {
+    let mut violations = Vec::new();
+    let mut check = |metric: String, limit: u64, actual: u64| {
+        if actual > limit {
+            violations.push(BudgetViolation {
+                metric,
+                limit,
+                actual,
+            });
+        }
+    };
+
+    if let Some(limit) = budget.max_size {
+        check("size".to_string(), limit.0, bytes.len() as u64);
+    }
+    if let Some(limit) = budget.max_gzip_size {
+        let actual = compress::gzip(bytes, MAX_GZIP_LEVEL)?.len() as u64;
+        check("gzip".to_string(), limit.0, actual);
+    }
+    if let Some(limit) = budget.max_brotli_size {
+        let actual = compress::brotli(bytes, MAX_BROTLI_QUALITY)?.len() as u64;
+        check("brotli".to_string(), limit.0, actual);
+    }
+
+    if !budget.sections.is_empty() && bytes.starts_with(b"\0asm") {
+        let report = SizeReport::analyze(bytes, None)?;
+        for (section, limit) in &budget.sections {
+            let actual = report.section_size(section).unwrap_or(0);
+            check(format!("section:{section}"), limit.0, actual);
+        }
+    }
+
+    Ok(violations)
+}
+
+/// Matches a file name against a pattern where `*` stands for any run of
+/// characters.
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    let Some(mut rest) = name
+        .strip_prefix(first)
+        .and_then(|rest| rest.strip_suffix(last))
+    else {
+        return false;
+    };
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matches_pattern;
+
+    #[test]
+    fn literal_pattern_matches_exactly() {
+        assert!(matches_pattern("index.wasm", "index.wasm"));
+        assert!(!matches_pattern("index.wasm", "index.wasm.map"));
+    }
+
+    #[test]
+    fn wildcards_match_any_run() {
+        assert!(matches_pattern("*.wasm", "release.wasm"));
+        assert!(matches_pattern("*", ""));
+        assert!(matches_pattern("rel*", "release.wasm"));
+        assert!(matches_pattern("*lease*", "release.wasm"));
+        assert!(matches_pattern("r*e*m", "release.wasm"));
+        assert!(!matches_pattern("*.wasm", "release.wasm.map"));
+        assert!(!matches_pattern("r*x*m", "release.wasm"));
+    }
+
+    #[test]
+    fn prefix_and_suffix_cannot_overlap() {
+        assert!(!matches_pattern("ab*ba", "aba"));
+        assert!(matches_pattern("ab*ba", "abba"));
+    }
+
+    #[test]
+    fn non_ascii_names_do_not_panic() {
+        assert!(!matches_pattern("*.wasm", "日本.map"));
+        assert!(matches_pattern("*.wasm", "日本.wasm"));
+        assert!(!matches_pattern("日*本", "日"));
+        assert!(matches_pattern("パッケージ-*", "パッケージ-core"));
+    }
+}
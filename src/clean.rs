@@ -0,0 +1,250 @@
+use crate::output::{self, LockPolicy, OutputLock};
+use crate::{BuildResult, PluginResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Per-project record of what builds wrote, relative to the project.
+pub const RECORD_FILE: &str = ".wasmasc/artifacts.json";
+
+/// Everything wasmasc has written for a project. Paths are absolute.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArtifactRecord {
+    /// Output directories builds have published into.
+    pub output_dirs: BTreeSet<PathBuf>,
+    /// Files builds published into those directories.
+    pub artifacts: BTreeSet<PathBuf>,
+    /// Files a build script wrote inside the project that wasmasc copied
+    /// its artifacts from. Only removed by `clean --all`.
+    pub intermediates: BTreeSet<PathBuf>,
+}
+
+impl ArtifactRecord {
+    pub fn load(project_path: impl AsRef<Path>) -> Self {
+        fs::read_to_string(project_path.as_ref().join(RECORD_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, project_path: &Path) -> PluginResult<()> {
+        let path = project_path.join(RECORD_FILE);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension(format!("{}.partial", std::process::id()));
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::write(&partial, json)?;
+        fs::rename(&partial, &path)?;
+        Ok(())
+    }
+
+    /// Read-modify-write under a lock next to the record, so builds into
+    /// different output directories don't drop each other's entries.
+    fn update(project_path: &Path, f: impl FnOnce(&mut Self)) -> PluginResult<()> {
+        let record_dir = project_path.join(RECORD_FILE);
+        let record_dir = record_dir.parent().unwrap_or(project_path);
+        let _lock = OutputLock::acquire(record_dir, LockPolicy::Wait)?;
+        let mut record = Self::load(project_path);
+        f(&mut record);
+        record.save(project_path)
+    }
+}
+
+/// Records the files a finished build published into `output_dir`.
+pub fn record_build(
+    project_path: impl AsRef<Path>,
+    output_dir: impl AsRef<Path>,
+    result: &BuildResult,
+) -> PluginResult<()> {
+    let output_dir = std::path::absolute(output_dir)?;
+    let files = std::iter::once(&result.wasm_path)
+        .chain(&result.js_path)
+        .chain(&result.additional_files)
+        .map(std::path::absolute)
+        .collect::<Result<Vec<_>, _>>()?;
+    ArtifactRecord::update(project_path.as_ref(), |record| {
+        record.output_dirs.insert(output_dir);
+        record.artifacts.extend(files);
+    })
+}
+
+/// Records files a build script wrote inside the project.
+pub fn record_intermediates(project_path: impl AsRef<Path>, files: &[PathBuf]) -> PluginResult<()> {
+    let files = files
+        .iter()
+        .map(std::path::absolute)
+        .collect::<Result<Vec<_>, _>>()?;
+    ArtifactRecord::update(project_path.as_ref(), |record| {
+        record.intermediates.extend(files);
+    })
+}
+
+/// What `clean` is going to remove. Build one with [`plan`], print it for a
+/// dry run, or [`CleanPlan::execute`] it.
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanPlan {
+    /// Existing files and directories that will be removed.
+    pub paths: Vec<PathBuf>,
+    #[serde(skip)]
+    project_path: PathBuf,
+    #[serde(skip)]
+    output_dirs: Vec<PathBuf>,
+    #[serde(skip)]
+    all: bool,
+}
+
+/// Works out what to clean for a project: the artifacts its builds published,
+/// plus staging directories, partial copies and lock files left in their
+/// output directories. With `all`, also the build script's own outputs, the
+/// files named by `asconfig.json` targets and the record itself.
+pub fn plan(project_path: impl AsRef<Path>, all: bool) -> CleanPlan {
+    let project_path = project_path.as_ref();
+    let project_path = &std::path::absolute(project_path).unwrap_or(project_path.to_path_buf());
+    let record = ArtifactRecord::load(project_path);
+
+    let mut paths: BTreeSet<PathBuf> = record.artifacts.clone();
+    for dir in &record.output_dirs {
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name == output::LOCK_FILE
+                || name.starts_with(output::STAGING_PREFIX)
+                || (name.starts_with('.') && name.ends_with(".partial"))
+            {
+                paths.insert(entry.path());
+            }
+        }
+    }
+
+    if all {
+        paths.extend(record.intermediates.iter().cloned());
+        for file in asconfig_outputs(project_path) {
+            paths.insert(crate::PathResolver::append_suffix(&file, ".map"));
+            paths.insert(file);
+        }
+        paths.insert(project_path.join(RECORD_FILE));
+    }
+
+    CleanPlan {
+        paths: paths.into_iter().filter(|p| p.exists()).collect(),
+        project_path: project_path.to_path_buf(),
+        output_dirs: record.output_dirs.into_iter().collect(),
+        all,
+    }
+}
+
+impl CleanPlan {
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Removes the planned paths, then any output directory left empty.
+    /// Fails with `PluginError::OutputLocked` while a build is running.
+    pub fn execute(&self) -> PluginResult<()> {
+        let locks = self
+            .output_dirs
+            .iter()
+            .filter(|dir| dir.is_dir())
+            .map(|dir| OutputLock::acquire(dir, LockPolicy::Fail))
+            .collect::<PluginResult<Vec<_>>>()?;
+
+        // Lock files go once the locks are released; elsewhere than Unix an
+        // open file can't be removed.
+        let (lock_files, paths): (Vec<_>, Vec<_>) = self
+            .paths
+            .iter()
+            .partition(|p| p.file_name().is_some_and(|n| n == output::LOCK_FILE));
+        for path in paths {
+            let removed = if path.is_dir() {
+                fs::remove_dir_all(path)
+            } else {
+                fs::remove_file(path)
+            };
+            match removed {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        drop(locks);
+        for path in lock_files {
+            let _ = fs::remove_file(path);
+        }
+
+        for dir in &self.output_dirs {
+            let _ = fs::remove_dir(dir);
+        }
+
+        let record = self.project_path.join(RECORD_FILE);
+        if self.all {
+            if let Some(dir) = record.parent() {
+                let _ = fs::remove_file(dir.join(output::LOCK_FILE));
+                let _ = fs::remove_dir(dir);
+            }
+            Ok(())
+        } else if record.exists() {
+            ArtifactRecord::update(&self.project_path, |record| {
+                record.artifacts.clear();
+                record.output_dirs.clear();
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// `outFile` and `textFile` of every target in `asconfig.json`.
+fn asconfig_outputs(project_path: &Path) -> Vec<PathBuf> {
+    let Some(asconfig) = fs::read_to_string(project_path.join("asconfig.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+    else {
+        return Vec::new();
+    };
+    let Some(targets) = asconfig["targets"].as_object() else {
+        return Vec::new();
+    };
+    targets
+        .values()
+        .flat_map(|target| [&target["outFile"], &target["textFile"]])
+        .filter_map(|file| file.as_str())
+        .map(|file| project_path.join(file))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{record_build, ArtifactRecord};
+    use crate::BuildResult;
+    use std::collections::BTreeMap;
+    use std::thread;
+
+    #[test]
+    fn concurrent_builds_keep_every_record() {
+        let project = tempfile::tempdir().unwrap();
+        thread::scope(|scope| {
+            for i in 0..8 {
+                let project = project.path();
+                scope.spawn(move || {
+                    let output_dir = project.join(format!("out{i}"));
+                    let result = BuildResult {
+                        wasm_path: output_dir.join("index.wasm"),
+                        js_path: None,
+                        additional_files: Vec::new(),
+                        is_wasm_bindgen: false,
+                        module_summary: None,
+                        optimization: None,
+                        manifest: None,
+                        integrity: BTreeMap::new(),
+                    };
+                    record_build(project, &output_dir, &result).unwrap();
+                });
+            }
+        });
+
+        let record = ArtifactRecord::load(project.path());
+        assert_eq!(record.output_dirs.len(), 8);
+        assert_eq!(record.artifacts.len(), 8);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::config::{PrecompressConfig, CONFIG_FILE_NAME};
+use crate::{BuildResult, PathResolver, PluginError, PluginResult};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sha2::{Digest, Sha384};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+pub const MAX_GZIP_LEVEL: u32 = 9;
+pub const MAX_BROTLI_QUALITY: u32 = 11;
+
+pub fn gzip(bytes: &[u8], level: u32) -> PluginResult<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level.min(MAX_GZIP_LEVEL)));
+    encoder.write_all(bytes).map_err(PluginError::Io)?;
+    encoder.finish().map_err(PluginError::Io)
+}
+
+pub fn brotli(bytes: &[u8], quality: u32) -> PluginResult<Vec<u8>> {
+    let mut output = Vec::new();
+    {
+        let mut writer =
+            brotli::CompressorWriter::new(&mut output, 4096, quality.min(MAX_BROTLI_QUALITY), 22);
+        writer.write_all(bytes).map_err(PluginError::Io)?;
+    }
+    Ok(output)
+}
+
+/// Writes `.gz` and `.br` copies of every `.wasm` in the result next to it
+/// and adds them to `additional_files`.
+pub fn precompress(result: &mut BuildResult, config: &PrecompressConfig) -> PluginResult<()> {
+    let out_of_range = |key: &str, max: u32| PluginError::InvalidConfig {
+        path: CONFIG_FILE_NAME.to_string(),
+        reason: format!("precompress.{key} must be between 0 and {max}"),
+    };
+    if config.gzip_level > MAX_GZIP_LEVEL {
+        return Err(out_of_range("gzip_level", MAX_GZIP_LEVEL));
+    }
+    if config.brotli_quality > MAX_BROTLI_QUALITY {
+        return Err(out_of_range("brotli_quality", MAX_BROTLI_QUALITY));
+    }
+
+    let wasm_files: Vec<PathBuf> = std::iter::once(&result.wasm_path)
+        .chain(&result.additional_files)
+        .filter(|path| path.extension().is_some_and(|ext| ext == "wasm"))
+        .cloned()
+        .collect();
+    for wasm in wasm_files {
+        let bytes = fs::read(&wasm)?;
+        if config.gzip {
+            let gz = PathResolver::append_suffix(&wasm, ".gz");
+            fs::write(&gz, gzip(&bytes, config.gzip_level)?)?;
+            result.additional_files.push(gz);
+        }
+        if config.brotli {
+            let br = PathResolver::append_suffix(&wasm, ".br");
+            fs::write(&br, brotli(&bytes, config.brotli_quality)?)?;
+            result.additional_files.push(br);
+        }
+    }
+    Ok(())
+}
+
+/// Subresource Integrity strings for the result's `.wasm` and `.js` files,
+/// the ones a page fetches. Compressed copies share their original's value,
+/// since integrity covers the decoded body.
+pub fn integrity(result: &BuildResult) -> PluginResult<BTreeMap<PathBuf, String>> {
+    std::iter::once(&result.wasm_path)
+        .chain(&result.js_path)
+        .chain(&result.additional_files)
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "wasm" || ext == "js" || ext == "mjs")
+        })
+        .map(|path| Ok((path.clone(), sri_sha384(&fs::read(path)?))))
+        .collect()
+}
+
+/// `sha384-<base64 digest>`, as used in `integrity` attributes.
+pub fn sri_sha384(bytes: &[u8]) -> String {
+    format!("sha384-{}", base64(&Sha384::digest(bytes)))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base64, sri_sha384};
+
+    #[test]
+    fn base64_pads_partial_chunks() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64(&[0xfb, 0xff, 0xbf]), "+/+/");
+    }
+
+    #[test]
+    fn sri_matches_known_digests() {
+        assert_eq!(
+            sri_sha384(b""),
+            "sha384-OLBgp1GsljhM2TJ+sbHjaiH9txEUvgdDTAzHv2P24donTt6/529l+9Ua0vFImLlb"
+        );
+        assert_eq!(
+            sri_sha384(b"abc"),
+            "sha384-ywB1P0WjXou1oD1pmsZQBycsMqsO3tFjGotgWkP/W+2AhgcroefMI1i67KE0yCWn"
+        );
+    }
+}
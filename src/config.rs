@@ -0,0 +1,218 @@
+use crate::{OptimizationLevel, PluginError, PluginResult};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+pub const CONFIG_FILE_NAME: &str = "wasmasc.toml";
+
+/// Per-project settings read from `wasmasc.toml` in the project root.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Size budgets keyed by artifact file name; `*` matches any characters.
+    pub budgets: BTreeMap<String, SizeBudget>,
+    /// Post-build Binaryen pass; absent means wasm-opt is not run.
+    pub wasm_opt: Option<WasmOptConfig>,
+    pub features: FeaturesConfig,
+    pub output: OutputConfig,
+    /// Compressed copies of each module; absent means none are written.
+    pub precompress: Option<PrecompressConfig>,
+}
+
+impl ProjectConfig {
+    /// Loads the project's config file, or the defaults when there is none.
+    pub fn load(project_path: impl AsRef<Path>) -> PluginResult<Self> {
+        let path = project_path.as_ref().join(CONFIG_FILE_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path).map_err(PluginError::Io)?;
+        toml::from_str(&content).map_err(|e| PluginError::InvalidConfig {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })
+    }
+}
+
+/// How published artifacts are named.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    /// Template for the module's file name without extension, e.g.
+    /// `[name].[hash:8]`. Files named after the module follow it.
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrecompressConfig {
+    pub gzip: bool,
+    /// 0 to 9.
+    pub gzip_level: u32,
+    pub brotli: bool,
+    /// 0 to 11.
+    pub brotli_quality: u32,
+}
+
+impl Default for PrecompressConfig {
+    fn default() -> Self {
+        Self {
+            gzip: true,
+            gzip_level: 9,
+            brotli: true,
+            brotli_quality: 11,
+        }
+    }
+}
+
+/// WebAssembly features on top of asc's defaults, using asc's `--enable` names.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeaturesConfig {
+    pub enable: Vec<String>,
+    pub disable: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WasmOptConfig {
+    pub enabled: bool,
+    /// Explicit wasm-opt binary; otherwise `node_modules/.bin` and `PATH` are searched.
+    pub path: Option<String>,
+    /// Pass lists per optimization level. An empty list skips wasm-opt for that level.
+    pub debug: Vec<String>,
+    pub release: Vec<String>,
+    pub size: Vec<String>,
+}
+
+impl Default for WasmOptConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: None,
+            debug: vec![],
+            release: vec!["-O3".to_string()],
+            size: vec![
+                "-Oz".to_string(),
+                "--converge".to_string(),
+                "--strip-debug".to_string(),
+            ],
+        }
+    }
+}
+
+impl WasmOptConfig {
+    pub fn passes(&self, level: &OptimizationLevel) -> &[String] {
+        match level {
+            OptimizationLevel::Debug => &self.debug,
+            OptimizationLevel::Release => &self.release,
+            OptimizationLevel::Size => &self.size,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SizeBudget {
+    pub max_size: Option<ByteSize>,
+    pub max_gzip_size: Option<ByteSize>,
+    pub max_brotli_size: Option<ByteSize>,
+    /// Limits on individual sections, keyed by name (`code`, `data`, `custom:name`).
+    pub sections: BTreeMap<String, ByteSize>,
+}
+
+/// A byte count written either as an integer or as a string such as `"64 KiB"`.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(into = "u64")]
+pub struct ByteSize(pub u64);
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bytes", self.0)
+    }
+}
+
+impl std::str::FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: f64 = number.parse().map_err(|_| format!("invalid size '{s}'"))?;
+
+        let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+            "" | "b" => 1.0,
+            "kb" => 1_000.0,
+            "kib" | "k" => 1_024.0,
+            "mb" => 1_000_000.0,
+            "mib" | "m" => 1_048_576.0,
+            other => return Err(format!("unknown size unit '{other}' in '{s}'")),
+        };
+
+        Ok(ByteSize((number * multiplier).round() as u64))
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Bytes(u64),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Bytes(bytes) => Ok(ByteSize(bytes)),
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ByteSize;
+
+    #[test]
+    fn parses_plain_and_unit_sizes() {
+        assert_eq!("1024".parse(), Ok(ByteSize(1024)));
+        assert_eq!("12 B".parse(), Ok(ByteSize(12)));
+        assert_eq!("64 KiB".parse(), Ok(ByteSize(65_536)));
+        assert_eq!("64k".parse(), Ok(ByteSize(65_536)));
+        assert_eq!("2kb".parse(), Ok(ByteSize(2_000)));
+        assert_eq!("1.5 MiB".parse(), Ok(ByteSize(1_572_864)));
+        assert_eq!(" 3 MB ".parse(), Ok(ByteSize(3_000_000)));
+    }
+
+    #[test]
+    fn rejects_bad_numbers_and_units() {
+        assert!("".parse::<ByteSize>().is_err());
+        assert!("KiB".parse::<ByteSize>().is_err());
+        assert!("10 GiB".parse::<ByteSize>().is_err());
+        assert!("1.2.3 KiB".parse::<ByteSize>().is_err());
+    }
+
+    #[test]
+    fn deserializes_integers_and_strings() {
+        #[derive(serde::Deserialize)]
+        struct Budget {
+            a: ByteSize,
+            b: ByteSize,
+        }
+        let budget: Budget = toml::from_str("a = 512\nb = \"1 KiB\"").unwrap();
+        assert_eq!((budget.a, budget.b), (ByteSize(512), ByteSize(1024)));
+        assert!(toml::from_str::<Budget>("a = 1\nb = \"1 parsec\"").is_err());
+    }
+}
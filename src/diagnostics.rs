@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single compiler message, such as `ERROR TS2304: Cannot find name 'foo'.`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Compiler code, e.g. `TS2304` or `AS100`.
+    pub code: String,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{file}")?;
+            if let (Some(line), Some(column)) = (self.line, self.column) {
+                write!(f, ":{line}:{column}")?;
+            }
+            write!(f, ": ")?;
+        }
+        write!(f, "{} {}", self.code, self.message)
+    }
+}
+
+pub(crate) fn describe(diagnostics: &[Diagnostic]) -> String {
+    let errors: Vec<String> = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .map(|d| d.to_string())
+        .collect();
+    match errors.len() {
+        1 => format!("1 error: {}", errors[0]),
+        n => format!("{n} errors:\n  {}", errors.join("\n  ")),
+    }
+}
+
+/// Extracts diagnostics from asc's console output. Each one starts with
+/// `ERROR <code>: <message>` and is followed by a source excerpt ending in
+/// `└─ in <file>(<line>,<column>)`.
+pub fn parse_asc_output(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for line in crate::test_runner::strip_ansi(output).lines() {
+        let line = line.trim();
+        if let Some(location) = line.strip_prefix("└─ in ") {
+            if let Some(last) = diagnostics.last_mut().filter(|d| d.file.is_none()) {
+                parse_location(last, location);
+            }
+            continue;
+        }
+
+        let (severity, rest) = if let Some(rest) = line.strip_prefix("ERROR ") {
+            (Severity::Error, rest)
+        } else if let Some(rest) = line.strip_prefix("WARNING ") {
+            (Severity::Warning, rest)
+        } else {
+            continue;
+        };
+        let Some((code, message)) = rest.split_once(": ") else {
+            continue;
+        };
+        if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+            continue;
+        }
+        diagnostics.push(Diagnostic {
+            severity,
+            code: code.to_string(),
+            message: message.to_string(),
+            file: None,
+            line: None,
+            column: None,
+        });
+    }
+    diagnostics
+}
+
+fn parse_location(diagnostic: &mut Diagnostic, location: &str) {
+    let Some((file, position)) = location.strip_suffix(')').and_then(|l| l.rsplit_once('(')) else {
+        diagnostic.file = Some(location.to_string());
+        return;
+    };
+    diagnostic.file = Some(file.to_string());
+    if let Some((line, column)) = position.split_once(',') {
+        diagnostic.line = line.parse().ok();
+        diagnostic.column = column.parse().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{describe, parse_asc_output, Severity};
+
+    #[test]
+    fn error_takes_location_from_its_excerpt() {
+        let output = "\
+ERROR TS2304: Cannot find name 'foo'.
+    :
+ 3 │ return foo + 1;
+   │        ~~~
+   └─ in assembly/index.ts(3,10)
+
+FAILURE 1 parse error(s)
+";
+        let diagnostics = parse_asc_output(output);
+        assert_eq!(diagnostics.len(), 1);
+        let error = &diagnostics[0];
+        assert_eq!(error.severity, Severity::Error);
+        assert_eq!(error.code, "TS2304");
+        assert_eq!(error.message, "Cannot find name 'foo'.");
+        assert_eq!(error.file.as_deref(), Some("assembly/index.ts"));
+        assert_eq!((error.line, error.column), (Some(3), Some(10)));
+        assert_eq!(
+            describe(&diagnostics),
+            "1 error: assembly/index.ts:3:10: TS2304 Cannot find name 'foo'."
+        );
+    }
+
+    #[test]
+    fn warnings_are_parsed_but_not_described_as_errors() {
+        let output = "\
+WARNING AS235: Only variables, functions and enums become WebAssembly module exports.
+   └─ in assembly/index.ts(1,1)
+ERROR AS100: Not implemented: Closures
+   └─ in assembly/index.ts(7,3)
+";
+        let diagnostics = parse_asc_output(output);
+        let severities: Vec<_> = diagnostics.iter().map(|d| d.severity).collect();
+        assert_eq!(severities, [Severity::Warning, Severity::Error]);
+        assert_eq!(diagnostics[0].code, "AS235");
+        assert_eq!(
+            describe(&diagnostics),
+            "1 error: assembly/index.ts:7:3: AS100 Not implemented: Closures"
+        );
+    }
+
+    #[test]
+    fn ansi_colours_are_ignored() {
+        let output = "\u{1b}[91mERROR\u{1b}[0m TS1005: ';' expected.\n\
+                      \u{1b}[90m   └─ in assembly/index.ts(2,5)\u{1b}[0m\n";
+        let diagnostics = parse_asc_output(output);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "TS1005");
+        assert_eq!(diagnostics[0].message, "';' expected.");
+        assert_eq!(diagnostics[0].line, Some(2));
+    }
+
+    #[test]
+    fn location_without_position_keeps_the_file() {
+        let output = "ERROR TS6054: File not found.\n   └─ in assembly/missing.ts\n";
+        let diagnostics = parse_asc_output(output);
+        assert_eq!(diagnostics[0].file.as_deref(), Some("assembly/missing.ts"));
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (None, None));
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "assembly/missing.ts: TS6054 File not found."
+        );
+    }
+}
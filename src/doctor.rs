@@ -0,0 +1,298 @@
+use crate::install::{self, InstallState};
+use crate::CommandExecutor;
+use crate::{package_manager, workspace};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Oldest Node major version supported by current AssemblyScript releases,
+/// used when the installed compiler does not declare `engines.node`.
+pub const MIN_NODE_MAJOR: u32 = 18;
+
+const PACKAGE_MANAGERS: &[&str] = &["npm", "pnpm", "yarn", "bun"];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to do about a warning or error.
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warning(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Warning,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn error(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Error,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DoctorReport {
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    pub fn has_errors(&self) -> bool {
+        self.checks.iter().any(|c| c.status == CheckStatus::Error)
+    }
+
+    pub fn problems(&self) -> impl Iterator<Item = &Check> {
+        self.checks.iter().filter(|c| c.status != CheckStatus::Ok)
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            let mark = match check.status {
+                CheckStatus::Ok => "✅",
+                CheckStatus::Warning => "⚠️ ",
+                CheckStatus::Error => "❌",
+            };
+            writeln!(f, "{mark} {}: {}", check.name, check.detail)?;
+            if let Some(fix) = &check.fix {
+                writeln!(f, "   → {fix}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks everything a build of `project_path` into `output_dir` depends on.
+pub fn diagnose(project_path: impl AsRef<Path>, output_dir: impl AsRef<Path>) -> DoctorReport {
+    let project = project_path.as_ref();
+    let package_json = workspace::read_package_json(project);
+    let root = workspace::install_root(project);
+    let local_asc = workspace::read_package_json(project.join("node_modules/assemblyscript"))
+        .or_else(|| workspace::read_package_json(root.join("node_modules/assemblyscript")));
+    let lockfiles = package_manager::find_lockfiles(project);
+    let detected = package_manager::detect(project);
+    let package_manager = detected.as_ref().map(|pm| pm.command()).unwrap_or("npm");
+
+    let mut checks = vec![check_node(local_asc.as_ref())];
+    checks.extend(check_asc(
+        project,
+        package_json.as_ref(),
+        local_asc.as_ref(),
+        package_manager,
+    ));
+    checks.extend(check_package_managers(&lockfiles));
+    checks.push(match &detected {
+        Ok(pm) => Check::ok("Package manager", format!("{pm} ({})", pm.reason)),
+        Err(e) => Check::error(
+            "Package manager",
+            e.to_string(),
+            "Install the package manager the project uses, or run `corepack enable`",
+        ),
+    });
+    if package_json.is_some() {
+        checks.push(check_node_modules(project, package_manager));
+    }
+    checks.push(check_output_dir(output_dir.as_ref()));
+
+    DoctorReport { checks }
+}
+
+fn check_node(local_asc: Option<&serde_json::Value>) -> Check {
+    let required = local_asc
+        .and_then(|asc| asc["engines"]["node"].as_str())
+        .and_then(|range| leading_number(range.trim_start_matches(|c: char| !c.is_ascii_digit())))
+        .unwrap_or(MIN_NODE_MAJOR);
+
+    match CommandExecutor::tool_version("node") {
+        None => Check::error(
+            "Node.js",
+            "not found on PATH",
+            format!("Install Node.js {required} or newer from https://nodejs.org"),
+        ),
+        Some(version) => match leading_number(version.trim_start_matches('v')) {
+            Some(major) if major < required => Check::error(
+                "Node.js",
+                format!("{version} is older than the v{required} asc requires"),
+                format!("Upgrade to Node.js {required} or newer (e.g. `nvm install {required}`)"),
+            ),
+            _ => Check::ok("Node.js", format!("{version} (asc needs v{required}+)")),
+        },
+    }
+}
+
+fn check_asc(
+    project: &Path,
+    package_json: Option<&serde_json::Value>,
+    local_asc: Option<&serde_json::Value>,
+    package_manager: &str,
+) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let local_version = local_asc.and_then(|asc| asc["version"].as_str());
+    let local_bin = workspace::find_bin(project, "asc").is_some();
+    let global_version =
+        CommandExecutor::tool_version("asc").map(|v| v.trim_start_matches("Version ").to_string());
+    let declared = package_json.and_then(|p| dependency_range(p, "assemblyscript"));
+
+    match (local_version, local_bin) {
+        (Some(version), true) => checks.push(Check::ok("Local asc", version)),
+        (Some(version), false) => checks.push(Check::warning(
+            "Local asc",
+            format!("assemblyscript {version} is installed but node_modules/.bin/asc is missing"),
+            format!("Reinstall dependencies with `{package_manager} install`"),
+        )),
+        (None, _) if declared.is_some() => checks.push(Check::error(
+            "Local asc",
+            "assemblyscript is listed in package.json but not installed",
+            format!("Run `{package_manager} install`"),
+        )),
+        (None, _) => {}
+    }
+
+    match (&global_version, local_version) {
+        (Some(global), Some(local)) if global != local => checks.push(Check::warning(
+            "Global asc",
+            format!("{global} differs from the project's {local}"),
+            "The project's version is used for npm builds; run `npx asc` to match it, or update the global install with `npm install -g assemblyscript@<version>`",
+        )),
+        (Some(global), _) => checks.push(Check::ok("Global asc", global.as_str())),
+        (None, None) => checks.push(Check::error(
+            "asc",
+            "no local or global AssemblyScript compiler found",
+            "Run `npm install --save-dev assemblyscript` in the project, or `npm install -g assemblyscript`",
+        )),
+        (None, Some(_)) => {}
+    }
+
+    checks
+}
+
+fn check_package_managers(lockfiles: &[(&str, &str)]) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let installed: Vec<String> = PACKAGE_MANAGERS
+        .iter()
+        .filter_map(|pm| CommandExecutor::tool_version(pm).map(|v| format!("{pm} {v}")))
+        .collect();
+    if installed.is_empty() {
+        checks.push(Check::error(
+            "Package managers",
+            "none of npm, pnpm, yarn or bun found",
+            "Install Node.js, which ships npm",
+        ));
+    } else {
+        checks.push(Check::ok("Package managers", installed.join(", ")));
+    }
+
+    match lockfiles {
+        [] => checks.push(Check::warning(
+            "Lockfile",
+            "none found",
+            "Run `npm install` (or your package manager's install) and commit the lockfile for reproducible builds",
+        )),
+        [(file, pm)] => checks.push(lockfile_check(file, pm)),
+        many => {
+            let names: Vec<&str> = many.iter().map(|(file, _)| *file).collect();
+            checks.push(Check::warning(
+                "Lockfile",
+                format!("multiple lockfiles: {}", names.join(", ")),
+                "Keep only the lockfile of the package manager you use, or set \"packageManager\" in package.json",
+            ));
+        }
+    }
+
+    checks
+}
+
+fn lockfile_check(file: &str, pm: &str) -> Check {
+    if CommandExecutor::tool_version(pm).is_some() {
+        Check::ok("Lockfile", format!("{file} ({pm})"))
+    } else {
+        Check::error(
+            "Lockfile",
+            format!("{file} requires {pm}, which is not installed"),
+            match pm {
+                "pnpm" | "yarn" => format!("Run `corepack enable` or `npm install -g {pm}`"),
+                "bun" => "Install bun from https://bun.sh".to_string(),
+                _ => "Install Node.js, which ships npm".to_string(),
+            },
+        )
+    }
+}
+
+fn check_node_modules(project_path: &Path, package_manager: &str) -> Check {
+    let state = install::install_state(project_path);
+    let fix = format!("Run `{package_manager} install`, or build with `--install auto`");
+    match state {
+        InstallState::UpToDate => Check::ok("node_modules", state.describe()),
+        InstallState::Stale => Check::warning("node_modules", state.describe(), fix),
+        InstallState::Missing | InstallState::Incomplete(_) => {
+            Check::error("node_modules", state.describe(), fix)
+        }
+    }
+}
+
+/// `output_dir` is taken as the build takes it: relative to the current
+/// directory, not the project.
+fn check_output_dir(path: &Path) -> Check {
+    // Probe the directory itself or, before the first build, its nearest
+    // existing ancestor.
+    let probe_dir = path
+        .ancestors()
+        .find(|p| p.is_dir())
+        .unwrap_or(Path::new("."));
+    let probe = probe_dir.join(format!(".wasmasc-doctor-{}", std::process::id()));
+
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            Check::ok(
+                "Output directory",
+                format!("{} is writable", path.display()),
+            )
+        }
+        Err(e) => Check::error(
+            "Output directory",
+            format!("cannot write to {}: {e}", probe_dir.display()),
+            "Fix the directory's permissions or pass a different --output directory",
+        ),
+    }
+}
+
+fn dependency_range<'a>(package_json: &'a serde_json::Value, name: &str) -> Option<&'a str> {
+    ["dependencies", "devDependencies"]
+        .iter()
+        .find_map(|section| package_json[section][name].as_str())
+}
+
+fn leading_number(text: &str) -> Option<u32> {
+    let digits: String = text.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
@@ -0,0 +1,430 @@
+use crate::{PluginError, PluginResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use wasmparser::{
+    CompositeInnerType, CustomSectionReader, ExternalKind, FuncType, KnownCustom, Name, Operator,
+    Parser, Payload, TypeRef, ValType,
+};
+
+/// Host imports the AssemblyScript runtime expects from the embedder.
+pub const ASSEMBLYSCRIPT_HOST_IMPORTS: &[&str] = &["abort", "trace", "seed"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImportInfo {
+    pub module: String,
+    pub name: String,
+    pub kind: String,
+    pub signature: Option<String>,
+    pub assemblyscript_runtime: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExportInfo {
+    pub name: String,
+    pub kind: String,
+    pub index: u32,
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LimitsInfo {
+    pub initial: u64,
+    pub maximum: Option<u64>,
+    pub shared: bool,
+    pub is_64: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TableInfo {
+    pub element_type: String,
+    pub limits: LimitsInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CustomSectionInfo {
+    pub name: String,
+    pub size: u64,
+}
+
+/// What a compiled module looks like from the outside.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ModuleSummary {
+    pub size: u64,
+    pub imports: Vec<ImportInfo>,
+    pub exports: Vec<ExportInfo>,
+    pub memories: Vec<LimitsInfo>,
+    pub tables: Vec<TableInfo>,
+    pub start_function: Option<u32>,
+    pub custom_sections: Vec<CustomSectionInfo>,
+    /// Post-MVP proposals the module relies on, using asc's `--enable` names.
+    pub features: Vec<String>,
+}
+
+impl ModuleSummary {
+    pub fn export(&self, name: &str) -> Option<&ExportInfo> {
+        self.exports.iter().find(|e| e.name == name)
+    }
+
+    pub fn uses_feature(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
+pub fn inspect_file(path: impl AsRef<Path>) -> PluginResult<ModuleSummary> {
+    let bytes = fs::read(path).map_err(PluginError::Io)?;
+    inspect_module(&bytes)
+}
+
+pub fn inspect_module(wasm: &[u8]) -> PluginResult<ModuleSummary> {
+    let mut summary = ModuleSummary {
+        size: wasm.len() as u64,
+        ..Default::default()
+    };
+    let mut types: Vec<Option<FuncType>> = Vec::new();
+    let mut func_types: Vec<u32> = Vec::new();
+    let mut features = BTreeSet::new();
+
+    for payload in Parser::new(0).parse_all(wasm) {
+        let payload = payload.map_err(parse_error)?;
+
+        match payload {
+            Payload::TypeSection(reader) => {
+                for group in reader {
+                    let group = group.map_err(parse_error)?;
+                    for ty in group.into_types() {
+                        match ty.composite_type.inner {
+                            CompositeInnerType::Func(func) => {
+                                if func.results().len() > 1 {
+                                    features.insert("multi-value");
+                                }
+                                if func.params().iter().chain(func.results()).any(is_v128) {
+                                    features.insert("simd");
+                                }
+                                if func.params().iter().chain(func.results()).any(is_ref) {
+                                    features.insert("reference-types");
+                                }
+                                types.push(Some(func));
+                            }
+                            _ => {
+                                features.insert("gc");
+                                types.push(None);
+                            }
+                        }
+                    }
+                }
+            }
+            Payload::ImportSection(reader) => {
+                for import in reader.into_imports() {
+                    let import = import.map_err(parse_error)?;
+                    let (kind, signature) = match import.ty {
+                        TypeRef::Func(idx) | TypeRef::FuncExact(idx) => {
+                            func_types.push(idx);
+                            ("func", signature(&types, idx))
+                        }
+                        TypeRef::Table(table) => {
+                            summary.tables.push(table_info(&table));
+                            ("table", None)
+                        }
+                        TypeRef::Memory(memory) => {
+                            summary.memories.push(memory_info(&memory));
+                            ("memory", None)
+                        }
+                        TypeRef::Global(global) => {
+                            if global.mutable {
+                                features.insert("mutable-globals");
+                            }
+                            ("global", Some(global.content_type.to_string()))
+                        }
+                        TypeRef::Tag(_) => {
+                            features.insert("exception-handling");
+                            ("tag", None)
+                        }
+                    };
+
+                    summary.imports.push(ImportInfo {
+                        module: import.module.to_string(),
+                        name: import.name.to_string(),
+                        kind: kind.to_string(),
+                        signature,
+                        assemblyscript_runtime: import.module == "env"
+                            && ASSEMBLYSCRIPT_HOST_IMPORTS.contains(&import.name),
+                    });
+                }
+            }
+            Payload::FunctionSection(reader) => {
+                for idx in reader {
+                    func_types.push(idx.map_err(parse_error)?);
+                }
+            }
+            Payload::TableSection(reader) => {
+                for table in reader {
+                    summary
+                        .tables
+                        .push(table_info(&table.map_err(parse_error)?.ty));
+                }
+            }
+            Payload::MemorySection(reader) => {
+                for memory in reader {
+                    summary
+                        .memories
+                        .push(memory_info(&memory.map_err(parse_error)?));
+                }
+            }
+            Payload::TagSection(_) => {
+                features.insert("exception-handling");
+            }
+            Payload::GlobalSection(reader) => {
+                for global in reader {
+                    let ty = global.map_err(parse_error)?.ty;
+                    if is_v128(&ty.content_type) {
+                        features.insert("simd");
+                    }
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export.map_err(parse_error)?;
+                    let signature = match export.kind {
+                        ExternalKind::Func | ExternalKind::FuncExact => func_types
+                            .get(export.index as usize)
+                            .and_then(|ty| signature(&types, *ty)),
+                        _ => None,
+                    };
+
+                    summary.exports.push(ExportInfo {
+                        name: export.name.to_string(),
+                        kind: external_kind_name(export.kind).to_string(),
+                        index: export.index,
+                        signature,
+                    });
+                }
+            }
+            Payload::StartSection { func, .. } => summary.start_function = Some(func),
+            Payload::DataCountSection { .. } => {
+                features.insert("bulk-memory");
+            }
+            Payload::CodeSectionEntry(body) => {
+                for op in body.get_operators_reader().map_err(parse_error)? {
+                    if let Some(feature) = operator_feature(&op.map_err(parse_error)?) {
+                        features.insert(feature);
+                    }
+                }
+            }
+            Payload::CustomSection(reader) => {
+                summary.custom_sections.push(CustomSectionInfo {
+                    name: reader.name().to_string(),
+                    size: reader.data().len() as u64,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    if summary.memories.len() > 1 {
+        features.insert("multi-memory");
+    }
+    if summary.tables.len() > 1 {
+        features.insert("reference-types");
+    }
+    for memory in &summary.memories {
+        if memory.shared {
+            features.insert("threads");
+        }
+        if memory.is_64 {
+            features.insert("memory64");
+        }
+    }
+
+    summary.features = features.into_iter().map(str::to_string).collect();
+    Ok(summary)
+}
+
+/// Function and data segment names from a module's `name` custom section.
+#[derive(Debug, Default)]
+pub(crate) struct ModuleNames {
+    pub functions: HashMap<u32, String>,
+    pub data: HashMap<u32, String>,
+}
+
+impl ModuleNames {
+    /// Merges names from `reader` if it is the `name` section; other custom
+    /// sections and malformed subsections are ignored.
+    pub fn read(&mut self, reader: &CustomSectionReader) {
+        if let KnownCustom::Name(names) = reader.as_known() {
+            for name in names.flatten() {
+                let (map, target) = match name {
+                    Name::Function(map) => (map, &mut self.functions),
+                    Name::Data(map) => (map, &mut self.data),
+                    _ => continue,
+                };
+                for naming in map.flatten() {
+                    target.insert(naming.index, naming.name.to_string());
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn parse_error(e: wasmparser::BinaryReaderError) -> PluginError {
+    PluginError::WasmParseFailed {
+        reason: e.to_string(),
+    }
+}
+
+fn signature(types: &[Option<FuncType>], idx: u32) -> Option<String> {
+    let func = types.get(idx as usize)?.as_ref()?;
+    Some(format_signature(func))
+}
+
+pub(crate) fn format_signature(func: &FuncType) -> String {
+    let join = |tys: &[ValType]| {
+        tys.iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match func.results() {
+        [] => format!("({})", join(func.params())),
+        [single] => format!("({}) -> {single}", join(func.params())),
+        results => format!("({}) -> ({})", join(func.params()), join(results)),
+    }
+}
+
+fn is_v128(ty: &ValType) -> bool {
+    matches!(ty, ValType::V128)
+}
+
+fn is_ref(ty: &ValType) -> bool {
+    matches!(ty, ValType::Ref(_))
+}
+
+fn memory_info(memory: &wasmparser::MemoryType) -> LimitsInfo {
+    LimitsInfo {
+        initial: memory.initial,
+        maximum: memory.maximum,
+        shared: memory.shared,
+        is_64: memory.memory64,
+    }
+}
+
+fn table_info(table: &wasmparser::TableType) -> TableInfo {
+    TableInfo {
+        element_type: table.element_type.to_string(),
+        limits: LimitsInfo {
+            initial: table.initial,
+            maximum: table.maximum,
+            shared: table.shared,
+            is_64: table.table64,
+        },
+    }
+}
+
+fn external_kind_name(kind: ExternalKind) -> &'static str {
+    match kind {
+        ExternalKind::Func | ExternalKind::FuncExact => "func",
+        ExternalKind::Table => "table",
+        ExternalKind::Memory => "memory",
+        ExternalKind::Global => "global",
+        ExternalKind::Tag => "tag",
+    }
+}
+
+fn operator_feature(op: &Operator) -> Option<&'static str> {
+    macro_rules! define_feature {
+        ($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident ($($ann:tt)*))*) => {
+            match op {
+                $( Operator::$op { .. } => define_feature!(name @$proposal), )*
+                _ => None,
+            }
+        };
+        (name @mvp) => { None };
+        (name @sign_extension) => { Some("sign-extension") };
+        (name @saturating_float_to_int) => { Some("nontrapping-f2i") };
+        (name @bulk_memory) => { Some("bulk-memory") };
+        (name @reference_types) => { Some("reference-types") };
+        (name @simd) => { Some("simd") };
+        (name @relaxed_simd) => { Some("relaxed-simd") };
+        (name @threads) => { Some("threads") };
+        (name @tail_call) => { Some("tail-calls") };
+        (name @exceptions) => { Some("exception-handling") };
+        (name @legacy_exceptions) => { Some("exception-handling") };
+        (name @gc) => { Some("gc") };
+        (name @function_references) => { Some("gc") };
+        (name @$other:ident) => { Some(stringify!($other)) };
+    }
+    wasmparser::for_each_operator!(define_feature)
+}
+
+impl fmt::Display for ModuleSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Size: {} bytes", self.size)?;
+
+        writeln!(f, "Imports ({}):", self.imports.len())?;
+        for import in &self.imports {
+            write!(f, "  {} {}.{}", import.kind, import.module, import.name)?;
+            if let Some(sig) = &import.signature {
+                write!(f, " {sig}")?;
+            }
+            if import.assemblyscript_runtime {
+                write!(f, " [AssemblyScript runtime]")?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "Exports ({}):", self.exports.len())?;
+        for export in &self.exports {
+            write!(f, "  {} {}", export.kind, export.name)?;
+            if let Some(sig) = &export.signature {
+                write!(f, " {sig}")?;
+            }
+            writeln!(f)?;
+        }
+
+        for (i, memory) in self.memories.iter().enumerate() {
+            writeln!(f, "Memory {i}: {}", format_limits(memory, "pages"))?;
+        }
+        for (i, table) in self.tables.iter().enumerate() {
+            writeln!(
+                f,
+                "Table {i}: {} {}",
+                table.element_type,
+                format_limits(&table.limits, "elements")
+            )?;
+        }
+
+        if let Some(start) = self.start_function {
+            writeln!(f, "Start function: {start}")?;
+        }
+
+        if !self.custom_sections.is_empty() {
+            writeln!(f, "Custom sections:")?;
+            for section in &self.custom_sections {
+                writeln!(f, "  {} ({} bytes)", section.name, section.size)?;
+            }
+        }
+
+        if self.features.is_empty() {
+            writeln!(f, "Features: mvp")
+        } else {
+            writeln!(f, "Features: {}", self.features.join(", "))
+        }
+    }
+}
+
+fn format_limits(limits: &LimitsInfo, unit: &str) -> String {
+    let mut out = match limits.maximum {
+        Some(max) => format!("{}..{max} {unit}", limits.initial),
+        None => format!("{}.. {unit}", limits.initial),
+    };
+    if limits.shared {
+        out.push_str(" shared");
+    }
+    if limits.is_64 {
+        out.push_str(" 64-bit");
+    }
+    out
+}
@@ -0,0 +1,160 @@
+use crate::package_manager::{self, PackageManager};
+use crate::workspace;
+use crate::{BuildConfig, PluginError, PluginResult};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+/// Files each package manager rewrites in `node_modules` on every install.
+const INSTALL_MARKERS: &[&str] = &[
+    ".package-lock.json",
+    ".modules.yaml",
+    ".yarn-integrity",
+    ".yarn-state.yml",
+];
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum InstallMode {
+    /// Install only when `node_modules` is missing or stale.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallState {
+    UpToDate,
+    Missing,
+    /// Packages declared in `package.json` that are not in `node_modules`.
+    Incomplete(Vec<String>),
+    /// The lockfile changed after the last install.
+    Stale,
+}
+
+impl InstallState {
+    pub fn needs_install(&self) -> bool {
+        *self != InstallState::UpToDate
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            InstallState::UpToDate => "installed".to_string(),
+            InstallState::Missing => "not installed".to_string(),
+            InstallState::Incomplete(missing) => {
+                format!("missing packages: {}", missing.join(", "))
+            }
+            InstallState::Stale => "older than the lockfile".to_string(),
+        }
+    }
+}
+
+/// Compares `node_modules` against `package.json` and the lockfile. Inside a
+/// workspace, packages may be hoisted to the root's `node_modules`.
+pub fn install_state(project_path: impl AsRef<Path>) -> InstallState {
+    let project = project_path.as_ref();
+    let root = workspace::install_root(project);
+    let module_dirs: Vec<PathBuf> = [project.join("node_modules"), root.join("node_modules")]
+        .into_iter()
+        .filter(|dir| dir.is_dir())
+        .collect();
+
+    let package_json = workspace::read_package_json(project);
+    let declared: Vec<String> = package_json
+        .iter()
+        .flat_map(|package| {
+            ["dependencies", "devDependencies"]
+                .iter()
+                .filter_map(|section| package[section].as_object())
+        })
+        .flat_map(|deps| deps.keys().cloned())
+        .collect();
+
+    // Package managers don't create node_modules for a project without dependencies.
+    if declared.is_empty() {
+        return InstallState::UpToDate;
+    }
+    if module_dirs.is_empty() {
+        return InstallState::Missing;
+    }
+
+    let missing: Vec<String> = declared
+        .into_iter()
+        .filter(|name| !module_dirs.iter().any(|dir| dir.join(name).exists()))
+        .collect();
+    if !missing.is_empty() {
+        return InstallState::Incomplete(missing);
+    }
+
+    let marker = module_dirs
+        .iter()
+        .flat_map(|dir| INSTALL_MARKERS.iter().map(move |marker| dir.join(marker)))
+        .filter_map(|marker| modified(&marker))
+        .max();
+    let lockfile = package_manager::find_lockfiles(project)
+        .iter()
+        .flat_map(|(file, _)| [project.join(file), root.join(file)])
+        .filter_map(|path| modified(&path))
+        .max();
+
+    match (lockfile, marker) {
+        (Some(lockfile), Some(marker)) if lockfile > marker => InstallState::Stale,
+        _ => InstallState::UpToDate,
+    }
+}
+
+/// Installs dependencies before a package-manager build, according to the
+/// config's install mode. Returns whether an install ran.
+pub fn ensure_dependencies(
+    config: &BuildConfig,
+    package_manager: &PackageManager,
+) -> PluginResult<bool> {
+    let state = install_state(&config.project_path);
+    let should_install = match config.install {
+        InstallMode::Never => false,
+        InstallMode::Always => true,
+        InstallMode::Auto => state.needs_install(),
+    };
+    if !should_install {
+        return Ok(false);
+    }
+
+    let cmd = package_manager.command();
+    let has_lockfile = package_manager.has_lockfile(&config.project_path);
+    let args = package_manager.install_args(has_lockfile, config.offline)?;
+
+    eprintln!(
+        "📦 Installing dependencies with {cmd} {} (node_modules {})...",
+        args.join(" "),
+        state.describe()
+    );
+    if config.verbose {
+        eprintln!("Executing: {cmd} {}", args.join(" "));
+    }
+
+    let output = Command::new(cmd)
+        .args(&args)
+        .envs(package_manager.install_env(config.offline))
+        .current_dir(workspace::install_root(&config.project_path))
+        .output()
+        .map_err(PluginError::Io)?;
+
+    if !output.status.success() {
+        return Err(PluginError::DependencyInstallFailed {
+            tool: cmd.to_string(),
+            reason: format!(
+                "{cmd} {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        });
+    }
+
+    Ok(true)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
@@ -0,0 +1,336 @@
+use crate::run::{AbortInfo, RunOutcome};
+use crate::{BuildConfig, PluginError, PluginResult, WasmBuilder};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use wasmi::errors::HostError;
+use wasmi::{
+    Caller, Engine, Error, Extern, ExternType, Linker, Memory, Module, Store, Val, ValType,
+};
+
+impl HostError for AbortInfo {}
+
+const HOST_IMPORTS: &[&str] = &["abort", "trace", "seed"];
+
+/// Builds the project, then calls `export` in the embedded interpreter.
+pub fn build_and_call(
+    builder: &dyn WasmBuilder,
+    config: &BuildConfig,
+    export: &str,
+    args: &[String],
+) -> PluginResult<RunOutcome> {
+    let result = builder.build(config)?;
+    call_export(&result.wasm_path, export, args)
+}
+
+/// Instantiates a module with AssemblyScript's `env` host functions and calls
+/// one of its exports with numeric arguments, without any JS runtime.
+///
+/// Traps and aborts are reported in the outcome; only loading and linking
+/// problems are errors.
+pub fn call_export(
+    wasm_path: impl AsRef<Path>,
+    export: &str,
+    args: &[String],
+) -> PluginResult<RunOutcome> {
+    let wasm_path = wasm_path.as_ref();
+    let wasm = fs::read(wasm_path).map_err(PluginError::Io)?;
+    let engine = Engine::default();
+    let module = Module::new(&engine, &wasm[..]).map_err(|e| PluginError::InvalidWasm {
+        path: wasm_path.display().to_string(),
+        offset: 0,
+        reason: e.to_string(),
+    })?;
+
+    let mut store = Store::new(&engine, ());
+    let mut linker = <Linker<()>>::new(&engine);
+    let imported_memory = define_imported_memory(&mut linker, &mut store, &module)?;
+    define_host_functions(&mut linker, &module, imported_memory).map_err(link_error)?;
+
+    let instance = match linker.instantiate_and_start(&mut store, &module) {
+        Ok(instance) => instance,
+        Err(e) => return Ok(outcome_from_error(e)),
+    };
+
+    let func = instance
+        .get_func(&store, export)
+        .ok_or_else(|| PluginError::ExportNotFound {
+            export: export.to_string(),
+        })?;
+
+    let ty = func.ty(&store);
+    if ty.params().len() != args.len() {
+        return Err(PluginError::InvalidArgument {
+            reason: format!(
+                "'{export}' takes {} argument(s), got {}",
+                ty.params().len(),
+                args.len()
+            ),
+        });
+    }
+
+    let inputs = ty
+        .params()
+        .iter()
+        .zip(args)
+        .map(|(ty, arg)| parse_arg(ty, arg))
+        .collect::<PluginResult<Vec<Val>>>()?;
+    let mut outputs: Vec<Val> = ty
+        .results()
+        .iter()
+        .map(|ty| Val::default_for_ty(*ty))
+        .collect();
+
+    match func.call(&mut store, &inputs, &mut outputs) {
+        Ok(()) => Ok(RunOutcome {
+            value: match outputs.len() {
+                0 => None,
+                1 => Some(format_val(&outputs[0])),
+                _ => Some(format!(
+                    "[{}]",
+                    outputs
+                        .iter()
+                        .map(format_val)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )),
+            },
+            ..Default::default()
+        }),
+        Err(e) => Ok(outcome_from_error(e)),
+    }
+}
+
+/// Modules built with `--importMemory` expect the host to provide their
+/// memory; it gets one of the declared size, which host functions then read
+/// strings from.
+fn define_imported_memory(
+    linker: &mut Linker<()>,
+    store: &mut Store<()>,
+    module: &Module,
+) -> PluginResult<Option<Memory>> {
+    let Some(import) = module
+        .imports()
+        .find(|import| matches!(import.ty(), ExternType::Memory(_)))
+    else {
+        return Ok(None);
+    };
+    let ExternType::Memory(ty) = import.ty() else {
+        return Ok(None);
+    };
+    let name = format!("{}.{}", import.module(), import.name());
+    let memory = Memory::new(&mut *store, *ty).map_err(|e| PluginError::InvalidConfig {
+        path: name.clone(),
+        reason: format!("cannot provide the imported memory: {e}"),
+    })?;
+    linker
+        .define(import.module(), import.name(), memory)
+        .map_err(|e| PluginError::RuntimeFailed {
+            reason: format!("Failed to link {name}: {e}"),
+        })?;
+    Ok(Some(memory))
+}
+
+fn define_host_functions(
+    linker: &mut Linker<()>,
+    module: &Module,
+    imported_memory: Option<Memory>,
+) -> Result<(), Error> {
+    linker.func_wrap(
+        "env",
+        "abort",
+        move |caller: Caller<'_, ()>,
+              msg: i32,
+              file: i32,
+              line: i32,
+              column: i32|
+              -> Result<(), Error> {
+            Err(Error::host(AbortInfo {
+                message: read_string(&caller, msg, imported_memory),
+                file: read_string(&caller, file, imported_memory),
+                line: line as u32,
+                column: column as u32,
+            }))
+        },
+    )?;
+
+    linker.func_wrap(
+        "env",
+        "trace",
+        move |caller: Caller<'_, ()>,
+              msg: i32,
+              n: i32,
+              a0: f64,
+              a1: f64,
+              a2: f64,
+              a3: f64,
+              a4: f64| {
+            let message = read_string(&caller, msg, imported_memory).unwrap_or_default();
+            let values: Vec<String> = [a0, a1, a2, a3, a4]
+                .iter()
+                .take(n.clamp(0, 5) as usize)
+                .map(|v| v.to_string())
+                .collect();
+            // On stderr, so it doesn't mix with the call's result.
+            if values.is_empty() {
+                eprintln!("trace: {message}");
+            } else {
+                eprintln!("trace: {message} {}", values.join(", "));
+            }
+        },
+    )?;
+
+    linker.func_wrap("env", "seed", || -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as f64)
+            .unwrap_or(0.0)
+    })?;
+
+    // Anything else the module imports traps when called, so modules with
+    // unrelated imports can still be instantiated and smoke-tested.
+    for import in module.imports() {
+        if let ExternType::Func(ty) = import.ty() {
+            if import.module() == "env" && HOST_IMPORTS.contains(&import.name()) {
+                continue;
+            }
+            let name = format!("{}.{}", import.module(), import.name());
+            linker.func_new(
+                import.module(),
+                import.name(),
+                ty.clone(),
+                move |_, _, _| Err(Error::new(format!("unresolved import {name} was called"))),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Decodes an AssemblyScript string: UTF-16LE code units preceded by their
+/// byte length in the object header.
+fn read_string(caller: &Caller<'_, ()>, ptr: i32, imported: Option<Memory>) -> Option<String> {
+    if ptr == 0 {
+        return None;
+    }
+    let memory = match caller.get_export("memory") {
+        Some(Extern::Memory(memory)) => memory,
+        _ => imported?,
+    };
+    let data = memory.data(caller);
+    let ptr = ptr as u32 as usize;
+
+    let header = data.get(ptr.checked_sub(4)?..ptr)?;
+    let len = u32::from_le_bytes(header.try_into().ok()?) as usize;
+    let bytes = data.get(ptr..ptr.checked_add(len)?)?;
+
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+fn parse_arg(ty: &ValType, arg: &str) -> PluginResult<Val> {
+    let invalid = || PluginError::InvalidArgument {
+        reason: format!("Cannot pass '{arg}' as {ty:?}"),
+    };
+
+    Ok(match ty {
+        ValType::I32 => Val::I32(
+            arg.parse::<i32>()
+                .or_else(|_| arg.parse::<u32>().map(|v| v as i32))
+                .map_err(|_| invalid())?,
+        ),
+        ValType::I64 => Val::I64(
+            arg.parse::<i64>()
+                .or_else(|_| arg.parse::<u64>().map(|v| v as i64))
+                .map_err(|_| invalid())?,
+        ),
+        ValType::F32 => Val::F32(arg.parse::<f32>().map_err(|_| invalid())?.into()),
+        ValType::F64 => Val::F64(arg.parse::<f64>().map_err(|_| invalid())?.into()),
+        _ => return Err(invalid()),
+    })
+}
+
+fn format_val(val: &Val) -> String {
+    match val {
+        Val::I32(v) => v.to_string(),
+        Val::I64(v) => v.to_string(),
+        Val::F32(v) => f32::from(*v).to_string(),
+        Val::F64(v) => f64::from(*v).to_string(),
+        other => format!("{other:?}"),
+    }
+}
+
+fn outcome_from_error(error: Error) -> RunOutcome {
+    match error.downcast_ref::<AbortInfo>() {
+        Some(abort) => RunOutcome {
+            abort: Some(abort.clone()),
+            ..Default::default()
+        },
+        None => RunOutcome {
+            error: Some(error.to_string()),
+            ..Default::default()
+        },
+    }
+}
+
+fn link_error(e: Error) -> PluginError {
+    PluginError::RuntimeFailed {
+        reason: format!("Failed to link host functions: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::call_export;
+    use std::fs;
+
+    fn section(id: u8, body: &[u8]) -> Vec<u8> {
+        let mut out = vec![id, body.len() as u8];
+        out.extend_from_slice(body);
+        out
+    }
+
+    /// What `asc --importMemory` produces in miniature: `fail()` aborts with
+    /// the message "hi", which lives in the imported, unexported memory.
+    fn imported_memory_module() -> Vec<u8> {
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        // (func (param i32 i32 i32 i32)), (func)
+        wasm.extend(section(
+            1,
+            &[2, 0x60, 4, 0x7f, 0x7f, 0x7f, 0x7f, 0, 0x60, 0, 0],
+        ));
+        let mut imports = vec![2];
+        imports.extend(b"\x03env\x06memory\x02\x00\x01");
+        imports.extend(b"\x03env\x05abort\x00\x00");
+        wasm.extend(section(2, &imports));
+        wasm.extend(section(3, &[1, 1]));
+        wasm.extend(section(7, b"\x01\x04fail\x00\x01"));
+        // abort(20, 0, 1, 2)
+        let body = [0x00, 0x41, 20, 0x41, 0, 0x41, 1, 0x41, 2, 0x10, 0x00, 0x0b];
+        let mut code = vec![1, body.len() as u8];
+        code.extend(body);
+        wasm.extend(section(10, &code));
+        // "hi" as UTF-16 at 20, after its byte length at 16.
+        wasm.extend(section(
+            11,
+            b"\x01\x00\x41\x10\x0b\x08\x04\x00\x00\x00h\x00i\x00",
+        ));
+        wasm
+    }
+
+    #[test]
+    fn imported_memory_is_provided_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("imported.wasm");
+        fs::write(&path, imported_memory_module()).unwrap();
+
+        let outcome = call_export(&path, "fail", &[]).unwrap();
+        let abort = outcome.abort.expect("fail() aborts");
+        assert_eq!(abort.message.as_deref(), Some("hi"));
+        assert_eq!(abort.file, None);
+        assert_eq!((abort.line, abort.column), (1, 2));
+    }
+}
@@ -1,30 +1,305 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{OsStr, OsString};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Output};
+use std::sync::{Mutex, OnceLock};
 use thiserror::Error;
 
+pub mod budget;
+pub mod clean;
+pub mod compress;
+pub mod config;
+pub mod diagnostics;
+pub mod doctor;
+pub mod inspect;
+pub mod install;
+#[cfg(feature = "interpreter")]
+pub mod interpreter;
+pub mod manifest;
+pub mod naming;
+pub mod output;
+pub mod package_manager;
+pub mod playground;
+pub mod reproducible;
+pub mod run;
+pub mod scaffold;
+pub mod serve;
+pub mod size;
+pub mod sourcemap;
+pub mod symbolicate;
+pub mod test_runner;
+pub mod validate;
+pub mod wasm_opt;
+pub mod webapp;
+pub mod workspace;
+
 #[derive(Error, Debug)]
 pub enum PluginError {
     #[error("Compilation failed: {reason}")]
     CompilationFailed { reason: String },
 
+    #[error("{tool} reported {}", diagnostics::describe(.diagnostics))]
+    Diagnostics {
+        tool: String,
+        diagnostics: Vec<diagnostics::Diagnostic>,
+    },
+
+    #[error("`{command}` failed{}: {output}", .status.map(|s| format!(" with exit code {s}")).unwrap_or_default())]
+    CommandFailed {
+        command: String,
+        status: Option<i32>,
+        output: String,
+    },
+
+    #[error("No \"{script}\" script in {package_json}")]
+    ScriptNotFound {
+        script: String,
+        package_json: String,
+    },
+
+    #[error("No WASM file found after build (searched {})", .searched.join(", "))]
+    ArtifactNotFound { searched: Vec<String> },
+
+    #[error("Build produced several WASM files: {}", .candidates.join(", "))]
+    AmbiguousArtifacts { candidates: Vec<String> },
+
     #[error("Compile tool not found: {tool}")]
     CompileToolNotFound { tool: String },
 
+    #[error("{tool} {found} does not match the required {required}")]
+    ToolVersionMismatch {
+        tool: String,
+        required: String,
+        found: String,
+    },
+
+    #[error("Dependency installation with {tool} failed: {reason}")]
+    DependencyInstallFailed { tool: String, reason: String },
+
     #[error("Invalid project structure: {reason}")]
     InvalidProjectStructure { reason: String },
 
     #[error("Missing entry file. Expected one of: {candidates:?}")]
     MissingEntryFile { candidates: Vec<String> },
 
+    #[error("No package.json found in {path}")]
+    MissingPackageJson { path: String },
+
+    #[error("Invalid config file {path}: {reason}")]
+    InvalidConfig { path: String, reason: String },
+
     #[error("Output directory creation failed: {path}")]
-    OutputDirectoryCreationFailed { path: String },
+    OutputDirectoryCreationFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Output directory {path} is locked by {holder}")]
+    OutputLocked { path: String, holder: String },
+
+    #[error("Invalid WASM module {path} at offset 0x{offset:x}: {reason}")]
+    InvalidWasm {
+        path: String,
+        offset: u64,
+        reason: String,
+    },
+
+    #[error("Failed to parse WASM module: {reason}")]
+    WasmParseFailed { reason: String },
+
+    #[error("Invalid source map: {reason}")]
+    InvalidSourceMap { reason: String },
+
+    #[error("Size budget exceeded for {artifact}: {}", budget::describe(.violations))]
+    SizeBudgetExceeded {
+        artifact: String,
+        violations: Vec<budget::BudgetViolation>,
+    },
+
+    #[error("Module has no exported function '{export}'")]
+    ExportNotFound { export: String },
+
+    #[error("Invalid argument: {reason}")]
+    InvalidArgument { reason: String },
+
+    #[error("Execution failed: {reason}")]
+    RuntimeFailed { reason: String },
+
+    #[error("Timed out after {seconds}s {operation}")]
+    Timeout { operation: String, seconds: u64 },
+
+    #[error("Cancelled: {operation}")]
+    Cancelled { operation: String },
+
+    #[error("Workspace package {package} failed")]
+    WorkspacePackageFailed {
+        package: String,
+        #[source]
+        source: Box<PluginError>,
+    },
 
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
 
+impl PluginError {
+    /// Stable identifier for the kind of error, for scripts and host tools.
+    pub fn code(&self) -> &'static str {
+        match self {
+            PluginError::CompilationFailed { .. } => "compilation_failed",
+            PluginError::Diagnostics { .. } => "diagnostics",
+            PluginError::CommandFailed { .. } => "command_failed",
+            PluginError::ScriptNotFound { .. } => "script_not_found",
+            PluginError::ArtifactNotFound { .. } => "artifact_not_found",
+            PluginError::AmbiguousArtifacts { .. } => "ambiguous_artifacts",
+            PluginError::CompileToolNotFound { .. } => "tool_not_found",
+            PluginError::ToolVersionMismatch { .. } => "tool_version_mismatch",
+            PluginError::DependencyInstallFailed { .. } => "dependency_install_failed",
+            PluginError::InvalidProjectStructure { .. } => "invalid_project",
+            PluginError::MissingEntryFile { .. } => "missing_entry_file",
+            PluginError::MissingPackageJson { .. } => "missing_package_json",
+            PluginError::InvalidConfig { .. } => "invalid_config",
+            PluginError::OutputDirectoryCreationFailed { .. } => "output_directory_failed",
+            PluginError::OutputLocked { .. } => "output_locked",
+            PluginError::InvalidWasm { .. } => "invalid_wasm",
+            PluginError::WasmParseFailed { .. } => "wasm_parse_failed",
+            PluginError::InvalidSourceMap { .. } => "invalid_source_map",
+            PluginError::SizeBudgetExceeded { .. } => "size_budget_exceeded",
+            PluginError::ExportNotFound { .. } => "export_not_found",
+            PluginError::InvalidArgument { .. } => "invalid_argument",
+            PluginError::RuntimeFailed { .. } => "runtime_failed",
+            PluginError::Timeout { .. } => "timeout",
+            PluginError::Cancelled { .. } => "cancelled",
+            PluginError::WorkspacePackageFailed { .. } => "workspace_package_failed",
+            PluginError::Io(_) => "io",
+        }
+    }
+
+    /// Numeric code reported through the C API; `0` means success.
+    pub fn ffi_code(&self) -> i32 {
+        match self {
+            PluginError::CompilationFailed { .. } => 1,
+            PluginError::Diagnostics { .. } => 2,
+            PluginError::CommandFailed { .. } => 3,
+            PluginError::ScriptNotFound { .. } => 4,
+            PluginError::ArtifactNotFound { .. } => 5,
+            PluginError::AmbiguousArtifacts { .. } => 6,
+            PluginError::CompileToolNotFound { .. } => 7,
+            PluginError::ToolVersionMismatch { .. } => 8,
+            PluginError::DependencyInstallFailed { .. } => 9,
+            PluginError::InvalidProjectStructure { .. } => 10,
+            PluginError::MissingEntryFile { .. } => 11,
+            PluginError::MissingPackageJson { .. } => 12,
+            PluginError::InvalidConfig { .. } => 13,
+            PluginError::OutputDirectoryCreationFailed { .. } => 14,
+            PluginError::OutputLocked { .. } => 15,
+            PluginError::InvalidWasm { .. } => 16,
+            PluginError::WasmParseFailed { .. } => 17,
+            PluginError::InvalidSourceMap { .. } => 18,
+            PluginError::SizeBudgetExceeded { .. } => 19,
+            PluginError::ExportNotFound { .. } => 20,
+            PluginError::InvalidArgument { .. } => 21,
+            PluginError::RuntimeFailed { .. } => 22,
+            PluginError::Timeout { .. } => 23,
+            PluginError::Cancelled { .. } => 24,
+            PluginError::WorkspacePackageFailed { .. } => 25,
+            PluginError::Io(_) => 26,
+        }
+    }
+
+    /// CLI exit status, grouped by what the user has to fix. A failed
+    /// workspace package exits with its own error's status.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PluginError::CompilationFailed { .. }
+            | PluginError::Diagnostics { .. }
+            | PluginError::CommandFailed { .. }
+            | PluginError::ArtifactNotFound { .. }
+            | PluginError::AmbiguousArtifacts { .. }
+            | PluginError::RuntimeFailed { .. } => 1,
+            PluginError::InvalidProjectStructure { .. }
+            | PluginError::MissingEntryFile { .. }
+            | PluginError::MissingPackageJson { .. }
+            | PluginError::ScriptNotFound { .. }
+            | PluginError::InvalidConfig { .. }
+            | PluginError::ExportNotFound { .. }
+            | PluginError::InvalidArgument { .. } => 2,
+            PluginError::CompileToolNotFound { .. }
+            | PluginError::ToolVersionMismatch { .. }
+            | PluginError::DependencyInstallFailed { .. } => 3,
+            PluginError::InvalidWasm { .. }
+            | PluginError::WasmParseFailed { .. }
+            | PluginError::InvalidSourceMap { .. }
+            | PluginError::SizeBudgetExceeded { .. } => 4,
+            PluginError::OutputDirectoryCreationFailed { .. }
+            | PluginError::OutputLocked { .. }
+            | PluginError::Io(_) => 5,
+            PluginError::Timeout { .. } => 124,
+            PluginError::Cancelled { .. } => 130,
+            PluginError::WorkspacePackageFailed { source, .. } => source.exit_code(),
+        }
+    }
+
+    /// What to do about the error, when there is something better to say
+    /// than the message itself.
+    pub fn hint(&self) -> Option<String> {
+        let hint = match self {
+            PluginError::Diagnostics { .. } => return None,
+            PluginError::ScriptNotFound { script, .. } => {
+                format!("Add a \"{script}\" script to package.json")
+            }
+            PluginError::ArtifactNotFound { .. } => {
+                "Make sure the build script writes a .wasm file to build/, dist/ or out/".to_string()
+            }
+            PluginError::AmbiguousArtifacts { .. } => {
+                "Delete stale .wasm files, or name the outputs debug.wasm and release.wasm".to_string()
+            }
+            PluginError::CompileToolNotFound { .. } => {
+                "Run `npm install --save-dev assemblyscript` in the project, or `wasmasc doctor` to see what is missing".to_string()
+            }
+            PluginError::ToolVersionMismatch { tool, .. } if tool == "assemblyscript" => {
+                "Reinstall from the lockfile (e.g. `npm ci`) so node_modules matches it"
+                    .to_string()
+            }
+            PluginError::ToolVersionMismatch { .. } => {
+                "Run `corepack enable` so the version pinned in package.json is used".to_string()
+            }
+            PluginError::DependencyInstallFailed { .. } => {
+                "Run the install yourself to see its full output, or build with `--install never`".to_string()
+            }
+            PluginError::MissingEntryFile { .. } => {
+                "Create assembly/index.ts, or start from a template with `wasmasc init`".to_string()
+            }
+            PluginError::MissingPackageJson { .. } => {
+                "Run `wasmasc init`, or `npm init` to create one".to_string()
+            }
+            PluginError::InvalidConfig { path, .. } => format!("Fix or remove {path}"),
+            PluginError::OutputDirectoryCreationFailed { .. } => {
+                "Check the directory's permissions or pass a different --output directory".to_string()
+            }
+            PluginError::OutputLocked { .. } => {
+                "Wait for the other build to finish, or build with `--lock wait`".to_string()
+            }
+            PluginError::InvalidWasm { .. } => {
+                "Check that the features enabled in wasmasc.toml match what the module uses".to_string()
+            }
+            PluginError::SizeBudgetExceeded { .. } => {
+                "Run `wasmasc size` to see what is large, or raise the budget in wasmasc.toml".to_string()
+            }
+            PluginError::ExportNotFound { .. } => {
+                "Run `wasmasc inspect` to list the module's exports".to_string()
+            }
+            PluginError::Timeout { .. } => "Retry, or allow more time".to_string(),
+            PluginError::WorkspacePackageFailed { source, .. } => return source.hint(),
+            _ => return None,
+        };
+        Some(hint)
+    }
+}
+
 pub type PluginResult<T> = Result<T, PluginError>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,45 +345,194 @@ pub enum OptimizationLevel {
     Size,
 }
 
+impl OptimizationLevel {
+    /// Lowercase name, as given on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            OptimizationLevel::Debug => "debug",
+            OptimizationLevel::Release => "release",
+            OptimizationLevel::Size => "size",
+        }
+    }
+
+    /// The `asconfig.json` target a build at this level uses.
+    pub fn asconfig_target(&self) -> &'static str {
+        match self {
+            OptimizationLevel::Debug => "debug",
+            OptimizationLevel::Release | OptimizationLevel::Size => "release",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildConfig {
-    pub project_path: String,
-    pub output_dir: String,
+    pub project_path: PathBuf,
+    pub output_dir: PathBuf,
     pub optimization_level: OptimizationLevel,
     pub verbose: bool,
     pub watch: bool,
+    /// When to install `node_modules` before a package-manager build.
+    #[serde(default)]
+    pub install: install::InstallMode,
+    /// Install from the package manager's local cache only.
+    #[serde(default)]
+    pub offline: bool,
+    /// What to do when another build holds the output directory.
+    #[serde(default)]
+    pub lock: output::LockPolicy,
+    /// Build with the lockfile's compiler, a pinned environment and
+    /// project-relative paths, so the same commit gives the same bytes.
+    #[serde(default)]
+    pub reproducible: bool,
+    /// Also emit ESM bindings, an `index.html` and a loader that serve the
+    /// module as a web app.
+    #[serde(default)]
+    pub webapp: bool,
+    /// Also emit ESM bindings and `playground.html`, a page for calling the
+    /// module's exports.
+    #[serde(default)]
+    pub playground: bool,
+}
+
+impl BuildConfig {
+    /// A release build of `project_path` into `output_dir` with default settings.
+    pub fn new(project_path: impl Into<PathBuf>, output_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            project_path: project_path.into(),
+            output_dir: output_dir.into(),
+            optimization_level: OptimizationLevel::Release,
+            verbose: false,
+            watch: false,
+            install: install::InstallMode::Auto,
+            offline: false,
+            lock: output::LockPolicy::Wait,
+            reproducible: false,
+            webapp: false,
+            playground: false,
+        }
+    }
+
+    #[deprecated(note = "lossy for non-UTF-8 paths; use `project_path`")]
+    pub fn project_path_str(&self) -> String {
+        self.project_path.to_string_lossy().into_owned()
+    }
+
+    #[deprecated(note = "lossy for non-UTF-8 paths; use `output_dir`")]
+    pub fn output_dir_str(&self) -> String {
+        self.output_dir.to_string_lossy().into_owned()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildResult {
-    pub wasm_path: String,
-    pub js_path: Option<String>,
-    pub additional_files: Vec<String>,
+    pub wasm_path: PathBuf,
+    pub js_path: Option<PathBuf>,
+    pub additional_files: Vec<PathBuf>,
     pub is_wasm_bindgen: bool,
+    #[serde(default)]
+    pub module_summary: Option<inspect::ModuleSummary>,
+    #[serde(default)]
+    pub optimization: Option<wasm_opt::OptimizationReport>,
+    /// What was built and how; also written to `wasmasc-manifest.json`.
+    #[serde(default)]
+    pub manifest: Option<manifest::BuildManifest>,
+    /// `sha384-...` Subresource Integrity values of the `.wasm` and `.js` files.
+    #[serde(default)]
+    pub integrity: BTreeMap<PathBuf, String>,
+}
+
+impl BuildResult {
+    /// A result built from the `String` paths `BuildResult` used to have.
+    #[deprecated(note = "construct `BuildResult` with `PathBuf` fields")]
+    pub fn from_strings(
+        wasm_path: String,
+        js_path: Option<String>,
+        additional_files: Vec<String>,
+        is_wasm_bindgen: bool,
+    ) -> Self {
+        Self {
+            wasm_path: wasm_path.into(),
+            js_path: js_path.map(PathBuf::from),
+            additional_files: additional_files.into_iter().map(PathBuf::from).collect(),
+            is_wasm_bindgen,
+            module_summary: None,
+            optimization: None,
+            manifest: None,
+            integrity: BTreeMap::new(),
+        }
+    }
+
+    #[deprecated(note = "lossy for non-UTF-8 paths; use `wasm_path`")]
+    pub fn wasm_path_str(&self) -> String {
+        self.wasm_path.to_string_lossy().into_owned()
+    }
+
+    #[deprecated(note = "lossy for non-UTF-8 paths; use `js_path`")]
+    pub fn js_path_str(&self) -> Option<String> {
+        self.js_path
+            .as_ref()
+            .map(|path| path.to_string_lossy().into_owned())
+    }
+
+    #[deprecated(note = "lossy for non-UTF-8 paths; use `additional_files`")]
+    pub fn additional_files_str(&self) -> Vec<String> {
+        self.additional_files
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    /// The result's files of one kind, e.g. `ArtifactKind::Playground`.
+    pub fn files_of_kind(&self, kind: manifest::ArtifactKind) -> Vec<&Path> {
+        std::iter::once(&self.wasm_path)
+            .chain(&self.js_path)
+            .chain(&self.additional_files)
+            .filter(|path| manifest::ArtifactKind::of(path) == kind)
+            .map(PathBuf::as_path)
+            .collect()
+    }
 }
 
 pub trait Plugin: Send + Sync {
     fn info(&self) -> &PluginInfo;
-    fn can_handle_project(&self, project_path: &str) -> bool;
+    fn can_handle_project(&self, project_path: &Path) -> bool;
     fn get_builder(&self) -> Box<dyn WasmBuilder>;
 }
 
 pub trait WasmBuilder: Send + Sync {
-    fn can_handle_project(&self, project_path: &str) -> bool;
+    fn can_handle_project(&self, project_path: &Path) -> bool;
     fn build(&self, config: &BuildConfig) -> PluginResult<BuildResult>;
     fn check_dependencies(&self) -> Vec<String>;
-    fn validate_project(&self, project_path: &str) -> PluginResult<()>;
-    fn clean(&self, project_path: &str) -> Result<(), Box<dyn std::error::Error>>;
+    fn validate_project(&self, project_path: &Path) -> PluginResult<()>;
+    fn clean(&self, project_path: &Path) -> PluginResult<()>;
     fn clone_box(&self) -> Box<dyn WasmBuilder>;
     fn language_name(&self) -> &str;
     fn entry_file_candidates(&self) -> &[&str];
     fn supported_extensions(&self) -> &[&str];
+
+    // The trait is used as `dyn WasmBuilder`, so these stay `&Path` rather
+    // than `impl AsRef<Path>`; the `*_str` forms keep `&str` callers working.
+
+    #[deprecated(note = "use `can_handle_project` with a `Path`")]
+    fn can_handle_project_str(&self, project_path: &str) -> bool {
+        self.can_handle_project(Path::new(project_path))
+    }
+
+    #[deprecated(note = "use `validate_project` with a `Path`")]
+    fn validate_project_str(&self, project_path: &str) -> PluginResult<()> {
+        self.validate_project(Path::new(project_path))
+    }
+
+    #[deprecated(note = "use `clean` with a `Path`")]
+    fn clean_str(&self, project_path: &str) -> PluginResult<()> {
+        self.clean(Path::new(project_path))
+    }
 }
 
 pub struct CommandExecutor;
 
 impl CommandExecutor {
-    pub fn is_tool_installed(tool: &str) -> bool {
+    pub fn is_tool_installed(tool: impl AsRef<OsStr>) -> bool {
         Command::new(tool)
             .arg("--version")
             .output()
@@ -116,14 +540,16 @@ impl CommandExecutor {
             .unwrap_or(false)
     }
 
-    pub fn execute_command(
-        cmd: &str,
-        args: &[&str],
-        cwd: &str,
+    pub fn execute_command<S: AsRef<OsStr>>(
+        cmd: impl AsRef<OsStr>,
+        args: &[S],
+        cwd: impl AsRef<Path>,
         verbose: bool,
     ) -> PluginResult<Output> {
+        let cmd = cmd.as_ref();
         if verbose {
-            println!("Executing: {} {}", cmd, args.join(" "));
+            let args: Vec<_> = args.iter().map(|a| a.as_ref().to_string_lossy()).collect();
+            eprintln!("Executing: {} {}", cmd.to_string_lossy(), args.join(" "));
         }
 
         Command::new(cmd)
@@ -133,53 +559,186 @@ impl CommandExecutor {
             .map_err(PluginError::Io)
     }
 
-    pub fn copy_to_output(src: &str, dst: &str, lang: &str) -> PluginResult<String> {
-        let src_path = Path::new(src);
-        if !src_path.exists() {
-            return Err(PluginError::CompilationFailed {
-                reason: format!("{lang} build completed but output file not found"),
+    /// Like [`CommandExecutor::execute_command`], with the environment
+    /// pinned for a reproducible build when `reproducible` is set.
+    pub fn execute_build_command<S: AsRef<OsStr>>(
+        cmd: impl AsRef<OsStr>,
+        args: &[S],
+        cwd: impl AsRef<Path>,
+        verbose: bool,
+        reproducible: bool,
+    ) -> PluginResult<Output> {
+        if !reproducible {
+            return Self::execute_command(cmd, args, cwd, verbose);
+        }
+        let cmd = cmd.as_ref();
+        if verbose {
+            let args: Vec<_> = args.iter().map(|a| a.as_ref().to_string_lossy()).collect();
+            eprintln!(
+                "Executing (reproducible): {} {}",
+                cmd.to_string_lossy(),
+                args.join(" ")
+            );
+        }
+
+        let mut command = Command::new(cmd);
+        command.args(args).current_dir(cwd);
+        reproducible::pin_environment(&mut command);
+        command.output().map_err(PluginError::Io)
+    }
+
+    /// Turns a failed command's output into an error: `Cancelled` when the
+    /// process was interrupted or killed, `Diagnostics` when it printed asc
+    /// errors, otherwise `CommandFailed` with its stderr.
+    pub fn command_error(command: impl Into<String>, output: &Output) -> PluginError {
+        let command = command.into();
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            if let Some(1 | 2 | 9 | 15) = output.status.signal() {
+                return PluginError::Cancelled { operation: command };
+            }
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let diagnostics = diagnostics::parse_asc_output(&format!("{stdout}\n{stderr}"));
+        if diagnostics
+            .iter()
+            .any(|d| d.severity == diagnostics::Severity::Error)
+        {
+            return PluginError::Diagnostics {
+                tool: command,
+                diagnostics,
+            };
+        }
+
+        PluginError::CommandFailed {
+            command,
+            status: output.status.code(),
+            output: match stderr.trim() {
+                "" => stdout.trim().to_string(),
+                stderr => stderr.to_string(),
+            },
+        }
+    }
+
+    /// First line of `tool --version`, or `None` when the tool is unavailable.
+    /// Probes are cached for the life of the process.
+    pub fn tool_version(tool: &str) -> Option<String> {
+        static PROBES: OnceLock<Mutex<HashMap<String, Option<String>>>> = OnceLock::new();
+        let probes = PROBES.get_or_init(|| Mutex::new(HashMap::new()));
+        if let Some(cached) = probes.lock().ok().and_then(|p| p.get(tool).cloned()) {
+            return cached;
+        }
+
+        let version = Command::new(tool)
+            .arg("--version")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .next()
+                    .map(|line| line.trim().to_string())
             });
+
+        if let Ok(mut probes) = probes.lock() {
+            probes.insert(tool.to_string(), version.clone());
         }
+        version
+    }
 
-        let filename = src_path.file_name().unwrap();
-        let dst_path = Path::new(dst).join(filename);
-        fs::copy(src_path, &dst_path).map_err(PluginError::Io)?;
+    pub fn copy_to_output(
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        lang: &str,
+    ) -> PluginResult<PathBuf> {
+        let (src_path, dst) = (src.as_ref(), dst.as_ref());
+        let filename = match src_path.file_name() {
+            Some(filename) if src_path.exists() => filename,
+            _ => {
+                return Err(PluginError::ArtifactNotFound {
+                    searched: vec![format!("{} ({lang} output)", src_path.display())],
+                })
+            }
+        };
 
-        Ok(dst_path.to_string_lossy().to_string())
+        let dst_path = dst.join(filename);
+        // Copy beside the destination and rename over it, so readers see
+        // either the old file or the new one.
+        let mut partial_name = OsString::from(".");
+        partial_name.push(filename);
+        partial_name.push(format!(".{}.partial", std::process::id()));
+        let partial = dst.join(partial_name);
+        fs::copy(src_path, &partial).map_err(PluginError::Io)?;
+        fs::rename(&partial, &dst_path).map_err(|e| {
+            let _ = fs::remove_file(&partial);
+            PluginError::Io(e)
+        })?;
+
+        Ok(dst_path)
     }
 }
 
 pub struct PathResolver;
 
 impl PathResolver {
+    #[deprecated(note = "lossy for non-UTF-8 paths; use `Path::join`")]
     pub fn join_paths(base: &str, rel: &str) -> String {
         Path::new(base).join(rel).to_string_lossy().to_string()
     }
 
-    pub fn validate_directory_exists(path: &str) -> PluginResult<()> {
-        let p = Path::new(path);
-        if !p.is_dir() {
+    /// `path` with `suffix` appended to its file name, e.g. `index.wasm` to
+    /// `index.wasm.map`, without a round trip through UTF-8.
+    pub fn append_suffix(path: impl AsRef<Path>, suffix: &str) -> PathBuf {
+        let mut appended = path.as_ref().as_os_str().to_os_string();
+        appended.push(suffix);
+        PathBuf::from(appended)
+    }
+
+    /// The `.js` and `.d.ts` bindings asc wrote next to `wasm_file`, when
+    /// there are any.
+    pub fn binding_files(wasm_file: impl AsRef<Path>) -> (Option<PathBuf>, Option<PathBuf>) {
+        let wasm_file = wasm_file.as_ref();
+        let existing = |ext: &str| {
+            let stem = wasm_file.file_stem()?;
+            let mut name = stem.to_os_string();
+            name.push(ext);
+            Some(wasm_file.with_file_name(name)).filter(|path| path.exists())
+        };
+        (existing(".js"), existing(".d.ts"))
+    }
+
+    pub fn validate_directory_exists(path: impl AsRef<Path>) -> PluginResult<()> {
+        let path = path.as_ref();
+        if !path.is_dir() {
             return Err(PluginError::InvalidProjectStructure {
-                reason: format!("Directory does not exist: {path}"),
+                reason: format!("Directory does not exist: {}", path.display()),
             });
         }
         Ok(())
     }
 
-    pub fn ensure_output_directory(path: &str) -> PluginResult<()> {
-        fs::create_dir_all(path).map_err(|_| PluginError::OutputDirectoryCreationFailed {
-            path: path.to_string(),
+    pub fn ensure_output_directory(path: impl AsRef<Path>) -> PluginResult<()> {
+        let path = path.as_ref();
+        fs::create_dir_all(path).map_err(|source| PluginError::OutputDirectoryCreationFailed {
+            path: path.display().to_string(),
+            source,
         })
     }
 
-    pub fn find_files_with_extension(path: &str, ext: &str) -> PluginResult<Vec<String>> {
+    pub fn find_files_with_extension(
+        path: impl AsRef<Path>,
+        ext: &str,
+    ) -> PluginResult<Vec<PathBuf>> {
         let mut files = Vec::new();
         if let Ok(entries) = fs::read_dir(path) {
             for entry in entries.flatten() {
-                if let Some(extension) = entry.path().extension() {
-                    if extension.to_string_lossy() == ext {
-                        files.push(entry.path().to_string_lossy().to_string());
-                    }
+                let path = entry.path();
+                if path.extension().is_some_and(|extension| extension == ext) {
+                    files.push(path);
                 }
             }
         }
@@ -213,7 +772,7 @@ impl AscPlugin {
             dependencies: vec![],
             capabilities: PluginCapabilities {
                 compile_wasm: true,
-                compile_webapp: false,
+                compile_webapp: true,
                 live_reload: true,
                 optimization: true,
                 custom_targets: vec!["wasm".to_string()],
@@ -223,16 +782,15 @@ impl AscPlugin {
         Self { info }
     }
 
-    fn is_asc_project(&self, project_path: &str) -> bool {
-        let package_json_path = PathResolver::join_paths(project_path, "package.json");
-        if let Ok(content) = fs::read_to_string(package_json_path) {
+    fn is_asc_project(&self, project_path: &Path) -> bool {
+        if let Ok(content) = fs::read_to_string(project_path.join("package.json")) {
             content.contains("asc") || content.contains("@asc")
         } else {
             false
         }
     }
 
-    fn find_entry_file(&self, project_path: &str) -> PluginResult<PathBuf> {
+    fn find_entry_file(&self, project_path: &Path) -> PluginResult<PathBuf> {
         let candidates = [
             "assembly/index.ts",
             "assembly/main.ts",
@@ -243,20 +801,14 @@ impl AscPlugin {
         ];
 
         for name in candidates.iter() {
-            let path = Path::new(project_path).join(name);
+            let path = project_path.join(name);
             if path.exists() {
                 return Ok(path);
             }
         }
 
-        for dir in &["assembly", "src", "."] {
-            let search_path = if *dir == "." {
-                project_path.to_string()
-            } else {
-                PathResolver::join_paths(project_path, dir)
-            };
-
-            if let Ok(entries) = fs::read_dir(&search_path) {
+        for dir in ["assembly", "src", "."] {
+            if let Ok(entries) = fs::read_dir(project_path.join(dir)) {
                 for entry in entries.flatten() {
                     if let Some(ext) = entry.path().extension() {
                         if ext == "ts" {
@@ -276,136 +828,312 @@ impl AscPlugin {
         })
     }
 
-    fn build_with_asc(&self, config: &BuildConfig) -> PluginResult<BuildResult> {
+    fn build_with_asc(
+        &self,
+        config: &BuildConfig,
+        project_config: &config::ProjectConfig,
+    ) -> PluginResult<BuildResult> {
         let entry_path = self.find_entry_file(&config.project_path)?;
         PathResolver::ensure_output_directory(&config.output_dir)?;
+        let asc = match config.reproducible {
+            true => reproducible::pinned_compiler(&config.project_path)?,
+            false => PathBuf::from("asc"),
+        };
+        // asc runs from the project, so a relative entry keeps absolute
+        // paths out of the module's names and source map.
+        let entry_arg = match config.reproducible {
+            true => entry_path
+                .strip_prefix(&config.project_path)
+                .unwrap_or(&entry_path),
+            false => &entry_path,
+        };
 
-        let output_name = entry_path
-            .file_stem()
-            .unwrap()
-            .to_string_lossy()
-            .to_string();
-        let wasm_file = Path::new(&config.output_dir).join(format!("{output_name}.wasm"));
-
-        println!("🔨 Building with AssemblyScript compiler...");
-
-        let mut args = vec![
-            entry_path.to_str().unwrap(),
-            "--target",
-            "release",
-            "--outFile",
-            wasm_file.to_str().unwrap(),
+        let output_name = entry_path.file_stem().unwrap_or(OsStr::new("index"));
+        let wasm_file = PathResolver::append_suffix(config.output_dir.join(output_name), ".wasm");
+
+        eprintln!("🔨 Building with AssemblyScript compiler...");
+
+        let mut args: Vec<&OsStr> = vec![
+            entry_arg.as_os_str(),
+            OsStr::new("--target"),
+            OsStr::new("release"),
+            OsStr::new("--outFile"),
+            wasm_file.as_os_str(),
         ];
 
-        match config.optimization_level {
-            OptimizationLevel::Debug => args.extend(&["--debug"]),
-            OptimizationLevel::Release => args.extend(&["--optimize"]),
-            OptimizationLevel::Size => args.extend(&["--optimize", "--shrinkLevel", "2"]),
+        let level_args: &[&str] = match config.optimization_level {
+            OptimizationLevel::Debug => &["--debug", "--sourceMap"],
+            OptimizationLevel::Release => &["--optimize", "--sourceMap"],
+            OptimizationLevel::Size => &["--optimize", "--shrinkLevel", "2", "--sourceMap"],
+        };
+        args.extend(level_args.iter().map(OsStr::new));
+        if config.webapp || config.playground {
+            args.extend([OsStr::new("--bindings"), OsStr::new("esm")]);
+        }
+
+        let enable = project_config.features.enable.join(",");
+        if !enable.is_empty() {
+            args.extend([OsStr::new("--enable"), OsStr::new(&enable)]);
+        }
+        let disable = project_config.features.disable.join(",");
+        if !disable.is_empty() {
+            args.extend([OsStr::new("--disable"), OsStr::new(&disable)]);
         }
 
-        let output =
-            CommandExecutor::execute_command("asc", &args, &config.project_path, config.verbose)?;
+        let output = CommandExecutor::execute_build_command(
+            &asc,
+            &args,
+            &config.project_path,
+            config.verbose,
+            config.reproducible,
+        )?;
 
         if !output.status.success() {
-            return Err(PluginError::CompilationFailed {
-                reason: String::from_utf8_lossy(&output.stderr).to_string(),
-            });
+            return Err(CommandExecutor::command_error("asc", &output));
         }
 
         if !wasm_file.exists() {
-            return Err(PluginError::CompilationFailed {
-                reason: "WASM file was not created".to_string(),
+            return Err(PluginError::ArtifactNotFound {
+                searched: vec![wasm_file.display().to_string()],
             });
         }
 
+        let mut additional_files = Vec::new();
+        let source_map = PathResolver::append_suffix(&wasm_file, ".map");
+        if source_map.exists() {
+            additional_files.push(source_map);
+        }
+        let (js_path, dts) = PathResolver::binding_files(&wasm_file);
+        additional_files.extend(dts);
+
+        let mut command = vec![asc.display().to_string()];
+        command.extend(args.iter().map(|a| a.to_string_lossy().into_owned()));
+        let manifest = manifest::BuildManifest {
+            entry_files: vec![entry_path.clone()],
+            compiler: manifest::Compiler::resolve(&config.project_path, !config.reproducible),
+            command,
+            ..Default::default()
+        };
+
         Ok(BuildResult {
-            wasm_path: wasm_file.to_string_lossy().to_string(),
-            js_path: None,
-            additional_files: vec![],
+            wasm_path: wasm_file,
+            js_path,
+            additional_files,
             is_wasm_bindgen: false,
+            module_summary: None,
+            optimization: None,
+            manifest: Some(manifest),
+            integrity: BTreeMap::new(),
         })
     }
 
-    fn build_with_npm(&self, config: &BuildConfig) -> PluginResult<BuildResult> {
-        let package_json = PathResolver::join_paths(&config.project_path, "package.json");
-        if !Path::new(&package_json).exists() {
-            return Err(PluginError::CompilationFailed {
-                reason: "No package.json found".to_string(),
-            });
-        }
-
-        let project_path = Path::new(&config.project_path);
-        let cmd = if CommandExecutor::is_tool_installed("bun")
-            && project_path.join("bun.lockb").exists()
-        {
-            "bun"
-        } else if CommandExecutor::is_tool_installed("pnpm")
-            && project_path.join("pnpm-lock.yaml").exists()
-        {
-            "pnpm"
-        } else if CommandExecutor::is_tool_installed("yarn")
-            && project_path.join("yarn.lock").exists()
-        {
-            "yarn"
-        } else if CommandExecutor::is_tool_installed("npm") {
-            "npm"
-        } else {
-            return Err(PluginError::CompileToolNotFound {
-                tool: "npm, pnpm, yarn, or bun".to_string(),
+    fn build_with_npm(
+        &self,
+        config: &BuildConfig,
+        package_manager: &package_manager::PackageManager,
+    ) -> PluginResult<BuildResult> {
+        let package_json = config.project_path.join("package.json");
+        let content = fs::read_to_string(&package_json).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => PluginError::MissingPackageJson {
+                path: config.project_path.display().to_string(),
+            },
+            _ => PluginError::Io(e),
+        })?;
+        let package: serde_json::Value =
+            serde_json::from_str(&content).map_err(|e| PluginError::InvalidConfig {
+                path: package_json.display().to_string(),
+                reason: e.to_string(),
+            })?;
+        let Some(script) = package["scripts"]["build"].as_str() else {
+            return Err(PluginError::ScriptNotFound {
+                script: "build".to_string(),
+                package_json: package_json.display().to_string(),
             });
         };
 
-        println!("🔨 Building with {cmd}...");
-        let args = match cmd {
-            "yarn" => vec!["build"],
-            "bun" => vec!["run", "build"],
-            _ => vec!["run", "build"],
-        };
+        let cmd = package_manager.command();
+        if config.verbose {
+            eprintln!("📦 Using {package_manager} ({})", package_manager.reason);
+        }
+
+        if config.reproducible {
+            reproducible::pinned_compiler(&config.project_path)?;
+        }
 
-        let output =
-            CommandExecutor::execute_command(cmd, &args, &config.project_path, config.verbose)?;
+        eprintln!("🔨 Building with {cmd}...");
+        let (cwd, args) =
+            workspace::script_command(&config.project_path, package_manager, "build", &[]);
+        let output = CommandExecutor::execute_build_command(
+            cmd,
+            &args,
+            &cwd,
+            config.verbose,
+            config.reproducible,
+        )?;
 
         if !output.status.success() {
-            return Err(PluginError::CompilationFailed {
-                reason: format!(
-                    "{} build failed: {}",
-                    cmd,
-                    String::from_utf8_lossy(&output.stderr)
-                ),
-            });
+            return Err(CommandExecutor::command_error(
+                format!("{cmd} run build"),
+                &output,
+            ));
         }
 
         let search_dirs = ["build", "dist", "out", "target", "."];
-        let mut wasm_files = Vec::new();
-
-        for dir in search_dirs {
-            let search_path = if dir == "." {
-                config.project_path.clone()
-            } else {
-                PathResolver::join_paths(&config.project_path, dir)
-            };
+        let wasm_files = search_dirs
+            .iter()
+            .map(|dir| {
+                PathResolver::find_files_with_extension(config.project_path.join(dir), "wasm")
+                    .unwrap_or_default()
+            })
+            .find(|files| !files.is_empty())
+            .ok_or_else(|| PluginError::ArtifactNotFound {
+                searched: search_dirs
+                    .iter()
+                    .map(|dir| config.project_path.join(dir).display().to_string())
+                    .collect(),
+            })?;
+        let wasm_file = Self::select_artifact(wasm_files, &config.optimization_level)?;
 
-            if let Ok(files) = PathResolver::find_files_with_extension(&search_path, "wasm") {
-                wasm_files.extend(files);
-            }
+        let output_path =
+            CommandExecutor::copy_to_output(&wasm_file, &config.output_dir, "AssemblyScript")?;
+
+        let mut additional_files = Vec::new();
+        let source_map = PathResolver::append_suffix(&wasm_file, ".map");
+        if source_map.exists() {
+            additional_files.push(CommandExecutor::copy_to_output(
+                &source_map,
+                &config.output_dir,
+                "AssemblyScript",
+            )?);
         }
-
-        if wasm_files.is_empty() {
-            return Err(PluginError::CompilationFailed {
-                reason: "No WASM file found after build".to_string(),
-            });
+        let (js_file, dts_file) = PathResolver::binding_files(&wasm_file);
+        let js_path = js_file
+            .as_ref()
+            .map(|js| CommandExecutor::copy_to_output(js, &config.output_dir, "AssemblyScript"))
+            .transpose()?;
+        if let Some(dts) = &dts_file {
+            additional_files.push(CommandExecutor::copy_to_output(
+                dts,
+                &config.output_dir,
+                "AssemblyScript",
+            )?);
         }
-
-        let output_path =
-            CommandExecutor::copy_to_output(&wasm_files[0], &config.output_dir, "AssemblyScript")?;
+        let mut intermediates = vec![wasm_file, source_map];
+        intermediates.extend(js_file.into_iter().chain(dts_file));
+        clean::record_intermediates(&config.project_path, &intermediates)?;
+
+        let mut command = vec![cmd.to_string()];
+        command.extend(args);
+        let manifest = manifest::BuildManifest {
+            entry_files: self.script_entry_files(&config.project_path),
+            compiler: manifest::Compiler::resolve(&config.project_path, false),
+            script: Some(manifest::Script {
+                package_manager: package_manager.to_string(),
+                name: "build".to_string(),
+                command: script.to_string(),
+            }),
+            command,
+            ..Default::default()
+        };
 
         Ok(BuildResult {
             wasm_path: output_path,
-            js_path: None,
-            additional_files: vec![],
+            js_path,
+            additional_files,
             is_wasm_bindgen: false,
+            module_summary: None,
+            optimization: None,
+            manifest: Some(manifest),
+            integrity: BTreeMap::new(),
+        })
+    }
+
+    /// Entry files of a script build: the `entries` of `asconfig.json`, or
+    /// the entry file a direct asc build would use.
+    fn script_entry_files(&self, project_path: &Path) -> Vec<PathBuf> {
+        let entries = fs::read_to_string(project_path.join("asconfig.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+            .and_then(|asconfig| {
+                asconfig["entries"].as_array().map(|entries| {
+                    entries
+                        .iter()
+                        .filter_map(|entry| entry.as_str())
+                        .map(|entry| project_path.join(entry))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .unwrap_or_default();
+        if entries.is_empty() {
+            self.find_entry_file(project_path).into_iter().collect()
+        } else {
+            entries
+        }
+    }
+
+    /// Picks the module a build script produced: the only one, or the one
+    /// named after the optimization level, as asconfig.json targets usually are.
+    fn select_artifact(
+        mut wasm_files: Vec<PathBuf>,
+        level: &OptimizationLevel,
+    ) -> PluginResult<PathBuf> {
+        if wasm_files.len() == 1 {
+            return Ok(wasm_files.remove(0));
+        }
+        let preferred = level.asconfig_target();
+        if let Some(index) = wasm_files
+            .iter()
+            .position(|f| f.file_stem().is_some_and(|stem| stem == preferred))
+        {
+            return Ok(wasm_files.remove(index));
+        }
+        wasm_files.sort();
+        Err(PluginError::AmbiguousArtifacts {
+            candidates: wasm_files.iter().map(|f| f.display().to_string()).collect(),
         })
     }
+
+    fn compile(
+        &self,
+        config: &BuildConfig,
+        project_config: &config::ProjectConfig,
+    ) -> PluginResult<BuildResult> {
+        if config.project_path.join("package.json").exists() {
+            let package_manager = match package_manager::detect(&config.project_path) {
+                Ok(package_manager) => package_manager,
+                Err(_) if CommandExecutor::is_tool_installed("asc") => {
+                    return self.build_with_asc(config, project_config);
+                }
+                Err(e) => return Err(e),
+            };
+
+            // Install failures are reported as-is rather than falling back to a
+            // global asc, which would hide the real problem.
+            install::ensure_dependencies(config, &package_manager)?;
+
+            // Fall back to a global asc only when the package manager could
+            // not build at all; a failing build script is reported as-is.
+            match self.build_with_npm(config, &package_manager) {
+                Err(PluginError::ScriptNotFound { .. } | PluginError::Io(_))
+                    if CommandExecutor::is_tool_installed("asc") =>
+                {
+                    self.build_with_asc(config, project_config)
+                }
+                Err(PluginError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                    Err(PluginError::CompileToolNotFound {
+                        tool: package_manager.command().to_string(),
+                    })
+                }
+                result => result,
+            }
+        } else if CommandExecutor::is_tool_installed("asc") {
+            self.build_with_asc(config, project_config)
+        } else {
+            Err(PluginError::CompileToolNotFound {
+                tool: "asc".to_string(),
+            })
+        }
+    }
 }
 
 impl Plugin for AscPlugin {
@@ -413,14 +1141,14 @@ impl Plugin for AscPlugin {
         &self.info
     }
 
-    fn can_handle_project(&self, project_path: &str) -> bool {
+    fn can_handle_project(&self, project_path: &Path) -> bool {
         if self.is_asc_project(project_path) {
             return true;
         }
 
         let assembly_files = ["assembly/index.ts", "assembly/main.ts"];
         for file in assembly_files {
-            if Path::new(&PathResolver::join_paths(project_path, file)).exists() {
+            if project_path.join(file).exists() {
                 return true;
             }
         }
@@ -456,7 +1184,8 @@ impl WasmBuilder for AscPlugin {
 
         if !CommandExecutor::is_tool_installed("asc") {
             missing.push(
-                "asc (AssemblyScript compiler - install with: npm install -g asc)".to_string(),
+                "asc (AssemblyScript compiler - install with: npm install -g assemblyscript)"
+                    .to_string(),
             );
         }
 
@@ -467,39 +1196,98 @@ impl WasmBuilder for AscPlugin {
         missing
     }
 
-    fn validate_project(&self, project_path: &str) -> PluginResult<()> {
+    fn validate_project(&self, project_path: &Path) -> PluginResult<()> {
         PathResolver::validate_directory_exists(project_path)?;
         let _ = self.find_entry_file(project_path)?;
         Ok(())
     }
 
     fn build(&self, config: &BuildConfig) -> PluginResult<BuildResult> {
-        if Path::new(&config.project_path)
-            .join("package.json")
-            .exists()
-        {
-            match self.build_with_npm(config) {
-                Ok(result) => Ok(result),
-                Err(_) => {
-                    if CommandExecutor::is_tool_installed("asc") {
-                        self.build_with_asc(config)
-                    } else {
-                        Err(PluginError::CompileToolNotFound {
-                            tool: "asc or npm/yarn".to_string(),
-                        })
-                    }
+        let project_config = config::ProjectConfig::load(&config.project_path)?;
+        let mut features = project_config.features.clone();
+        let asconfig = validate::asconfig_features(
+            &config.project_path,
+            config.optimization_level.asconfig_target(),
+        );
+        features.enable.extend(asconfig.enable);
+        features.disable.extend(asconfig.disable);
+        let features = validate::allowed_features(&features)?;
+
+        // Everything is built into a staging directory and only moved into
+        // the output directory once it has been validated and optimized.
+        let _lock = output::OutputLock::acquire(&config.output_dir, config.lock)?;
+        let staging = output::Staging::create(&config.output_dir)?;
+        let staged_config = BuildConfig {
+            output_dir: staging.path().to_path_buf(),
+            ..config.clone()
+        };
+        let mut result = self.compile(&staged_config, &project_config)?;
+
+        for artifact in std::iter::once(&result.wasm_path).chain(&result.additional_files) {
+            if artifact.extension().is_some_and(|ext| ext == "wasm") {
+                validate::validate_file(artifact, features)?;
+            }
+        }
+
+        if let Some(wasm_opt_config) = &project_config.wasm_opt {
+            result.optimization =
+                wasm_opt::optimize(&result.wasm_path, &staged_config, wasm_opt_config, features)?;
+        }
+
+        if config.reproducible {
+            for file in &result.additional_files {
+                if file.extension().is_some_and(|ext| ext == "map") {
+                    reproducible::normalize_source_map(file, &config.project_path)?;
                 }
             }
-        } else if CommandExecutor::is_tool_installed("asc") {
-            self.build_with_asc(config)
-        } else {
-            Err(PluginError::CompileToolNotFound {
-                tool: "asc".to_string(),
-            })
         }
+
+        if let Some(template) = &project_config.output.name {
+            naming::apply(&mut result, template, &config.optimization_level)?;
+        }
+        if config.webapp {
+            webapp::generate(&mut result, &config.project_path)?;
+        }
+        if let Some(precompress) = &project_config.precompress {
+            compress::precompress(&mut result, precompress)?;
+        }
+
+        result.module_summary = inspect::inspect_file(&result.wasm_path).ok();
+        if config.playground {
+            playground::generate(&mut result, &config.project_path)?;
+        }
+
+        // Checked on the staged files, so a build over budget never replaces
+        // the previous one.
+        let mut artifacts = vec![result.wasm_path.clone()];
+        artifacts.extend(result.additional_files.iter().cloned());
+        budget::check_budgets(&project_config, &artifacts).map_err(|e| match e {
+            PluginError::SizeBudgetExceeded {
+                artifact,
+                violations,
+            } => PluginError::SizeBudgetExceeded {
+                artifact: staging.published_path(&artifact).display().to_string(),
+                violations,
+            },
+            e => e,
+        })?;
+
+        staging.publish(&mut result)?;
+        result.integrity = compress::integrity(&result)?;
+
+        let mut manifest = result.manifest.take().unwrap_or_default();
+        manifest.finish(config, &project_config, &result)?;
+        result
+            .additional_files
+            .push(manifest.write(&config.output_dir)?);
+        result.manifest = Some(manifest);
+
+        clean::record_build(&config.project_path, &config.output_dir, &result)?;
+
+        Ok(result)
     }
 
-    fn can_handle_project(&self, project_path: &str) -> bool {
+    fn can_handle_project(&self, project_path: &Path) -> bool {
         if let Ok(entries) = fs::read_dir(project_path) {
             for entry in entries.flatten() {
                 if let Some(ext) = entry.path().extension() {
@@ -514,7 +1302,7 @@ impl WasmBuilder for AscPlugin {
         }
 
         for candidate in self.entry_file_candidates() {
-            if Path::new(project_path).join(candidate).exists() {
+            if project_path.join(candidate).exists() {
                 return true;
             }
         }
@@ -522,12 +1310,8 @@ impl WasmBuilder for AscPlugin {
         false
     }
 
-    fn clean(&self, project_path: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let build_dir = Path::new(project_path).join("build");
-        if build_dir.exists() {
-            fs::remove_dir_all(build_dir)?;
-        }
-        Ok(())
+    fn clean(&self, project_path: &Path) -> PluginResult<()> {
+        clean::plan(project_path, false).execute()
     }
 
     fn clone_box(&self) -> Box<dyn WasmBuilder> {
@@ -548,6 +1332,39 @@ pub fn create_plugin() -> Box<dyn Plugin> {
 use std::ffi::{c_char, c_void, CStr, CString};
 use std::ptr;
 
+/// Reads a path passed across the FFI boundary. On Unix any byte string is a
+/// valid path; elsewhere it must be UTF-8.
+///
+/// # Safety
+///
+/// `path` must be null or a valid null-terminated C string.
+unsafe fn path_from_c(path: *const c_char) -> Option<PathBuf> {
+    if path.is_null() {
+        return None;
+    }
+    let bytes = CStr::from_ptr(path).to_bytes();
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Some(PathBuf::from(OsStr::from_bytes(bytes)))
+    }
+    #[cfg(not(unix))]
+    {
+        std::str::from_utf8(bytes).ok().map(PathBuf::from)
+    }
+}
+
+fn path_to_c(path: &Path) -> *mut c_char {
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes().to_vec()
+    };
+    #[cfg(not(unix))]
+    let bytes = path.to_string_lossy().into_owned().into_bytes();
+    CString::new(bytes).unwrap_or_default().into_raw()
+}
+
 #[repr(C)]
 pub struct BuildConfigC {
     pub project_path: *const c_char,
@@ -557,6 +1374,15 @@ pub struct BuildConfigC {
     pub watch: bool,
 }
 
+/// Options added after `BuildConfigC` was frozen, passed to
+/// `wasmasc_build_with_options` so existing callers keep their layout.
+#[repr(C)]
+pub struct BuildOptionsC {
+    pub reproducible: bool,
+    pub webapp: bool,
+    pub playground: bool,
+}
+
 #[repr(C)]
 pub struct BuildResultC {
     pub wasm_path: *mut c_char,
@@ -566,6 +1392,18 @@ pub struct BuildResultC {
     pub error_message: *mut c_char,
 }
 
+thread_local! {
+    static LAST_ERROR_CODE: std::cell::Cell<i32> = const { std::cell::Cell::new(0) };
+}
+
+/// `PluginError::ffi_code` of the calling thread's last `wasmasc_build` or
+/// `wasmasc_build_with_options`, or `0` if it succeeded. Kept out of
+/// `BuildResultC` so existing callers keep its layout.
+#[no_mangle]
+pub extern "C" fn wasmasc_last_error_code() -> i32 {
+    LAST_ERROR_CODE.with(|code| code.get())
+}
+
 #[no_mangle]
 pub extern "C" fn wasmasc_plugin_create() -> *mut c_void {
     let plugin = Box::new(AscPlugin::new());
@@ -596,12 +1434,11 @@ pub unsafe extern "C" fn wasmasc_can_handle_project(
     }
 
     let builder = &*(builder_ptr as *const AscPlugin);
-    let path = match CStr::from_ptr(project_path).to_str() {
-        Ok(s) => s,
-        Err(_) => return false,
+    let Some(path) = path_from_c(project_path) else {
+        return false;
     };
 
-    WasmBuilder::can_handle_project(builder, path)
+    WasmBuilder::can_handle_project(builder, &path)
 }
 
 #[no_mangle]
@@ -611,13 +1448,27 @@ pub unsafe extern "C" fn wasmasc_can_handle_project(
 /// Callers must ensure that:
 /// - `builder_ptr` is a valid pointer to an `AscPlugin` instance (or null)
 /// - `config` is a valid pointer to a `BuildConfigC` struct (or null)
-/// - All C string pointers in `config` are valid null-terminated strings
+/// - All C string pointers in `config` are valid null-terminated strings (or null)
 ///
-/// Returns a pointer to an owned `BuildResultC` that must be freed by the caller.
-/// If either pointer is null, returns null.
+/// Paths are OS-native bytes on Unix and UTF-8 elsewhere, both in `config` and
+/// in the returned `BuildResultC`, which is owned and must be freed by the caller.
+/// If either pointer or either path in `config` is null, returns null.
 pub unsafe extern "C" fn wasmasc_build(
     builder_ptr: *const c_void,
     config: *const BuildConfigC,
+) -> *mut BuildResultC {
+    wasmasc_build_with_options(builder_ptr, config, ptr::null())
+}
+
+#[no_mangle]
+/// # Safety
+///
+/// Same contract as `wasmasc_build`; additionally `options` must be a valid
+/// pointer to a `BuildOptionsC` struct, or null for all options off.
+pub unsafe extern "C" fn wasmasc_build_with_options(
+    builder_ptr: *const c_void,
+    config: *const BuildConfigC,
+    options: *const BuildOptionsC,
 ) -> *mut BuildResultC {
     if builder_ptr.is_null() || config.is_null() {
         return ptr::null_mut();
@@ -626,14 +1477,11 @@ pub unsafe extern "C" fn wasmasc_build(
     let builder = &*(builder_ptr as *const AscPlugin);
     let config_c = &*config;
 
-    let project_path = match CStr::from_ptr(config_c.project_path).to_str() {
-        Ok(s) => s.to_string(),
-        Err(_) => return ptr::null_mut(),
-    };
-
-    let output_dir = match CStr::from_ptr(config_c.output_dir).to_str() {
-        Ok(s) => s.to_string(),
-        Err(_) => return ptr::null_mut(),
+    let (Some(project_path), Some(output_dir)) = (
+        path_from_c(config_c.project_path),
+        path_from_c(config_c.output_dir),
+    ) else {
+        return ptr::null_mut();
     };
 
     let opt_level = match config_c.optimization_level {
@@ -643,30 +1491,42 @@ pub unsafe extern "C" fn wasmasc_build(
         _ => OptimizationLevel::Release,
     };
 
+    let options = options.as_ref().unwrap_or(&BuildOptionsC {
+        reproducible: false,
+        webapp: false,
+        playground: false,
+    });
+
     let build_cfg = BuildConfig {
         project_path,
         output_dir,
         optimization_level: opt_level,
         verbose: config_c.verbose,
         watch: config_c.watch,
+        install: install::InstallMode::Auto,
+        offline: false,
+        lock: output::LockPolicy::Wait,
+        reproducible: options.reproducible,
+        webapp: options.webapp,
+        playground: options.playground,
     };
 
     match builder.build(&build_cfg) {
         Ok(result) => {
-            let wasm_path = CString::new(result.wasm_path).unwrap_or_default();
             let js_path = result
                 .js_path
-                .and_then(|p| CString::new(p).ok())
-                .map(|s| s.into_raw())
+                .as_deref()
+                .map(path_to_c)
                 .unwrap_or(ptr::null_mut());
 
             let result_c = Box::new(BuildResultC {
-                wasm_path: wasm_path.into_raw(),
+                wasm_path: path_to_c(&result.wasm_path),
                 js_path,
                 is_wasm_bindgen: result.is_wasm_bindgen,
                 success: true,
                 error_message: ptr::null_mut(),
             });
+            LAST_ERROR_CODE.with(|code| code.set(0));
 
             Box::into_raw(result_c)
         }
@@ -679,6 +1539,7 @@ pub unsafe extern "C" fn wasmasc_build(
                 success: false,
                 error_message: error_msg.into_raw(),
             });
+            LAST_ERROR_CODE.with(|code| code.set(e.ffi_code()));
 
             Box::into_raw(result_c)
         }
@@ -703,12 +1564,11 @@ pub unsafe extern "C" fn wasmasc_clean(
     }
 
     let builder = &*(builder_ptr as *const AscPlugin);
-    let path = match CStr::from_ptr(project_path).to_str() {
-        Ok(s) => s,
-        Err(_) => return false,
+    let Some(path) = path_from_c(project_path) else {
+        return false;
     };
 
-    builder.clean(path).is_ok()
+    builder.clean(&path).is_ok()
 }
 
 #[no_mangle]
@@ -762,7 +1622,7 @@ pub static WASMASC_PLUGIN_AUTHOR: &[u8] = b"Wasmrun Team\0";
 pub static WASMASC_SUPPORTS_WASM: bool = true;
 
 #[no_mangle]
-pub static WASMASC_SUPPORTS_WEBAPP: bool = false;
+pub static WASMASC_SUPPORTS_WEBAPP: bool = true;
 
 #[no_mangle]
 pub static WASMASC_SUPPORTS_LIVE_RELOAD: bool = true;
@@ -1,7 +1,23 @@
 #[cfg(feature = "cli")]
 use clap::{Parser, Subcommand};
 #[cfg(feature = "cli")]
-use wasmasc::{AscPlugin, BuildConfig, OptimizationLevel, Plugin, WasmBuilder};
+use std::io::Read;
+#[cfg(feature = "cli")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "cli")]
+use wasmasc::install::InstallMode;
+#[cfg(feature = "cli")]
+use wasmasc::output::LockPolicy;
+#[cfg(feature = "cli")]
+use wasmasc::run::RunOptions;
+#[cfg(feature = "cli")]
+use wasmasc::scaffold::{InitOptions, Template};
+#[cfg(feature = "cli")]
+use wasmasc::serve::ServeOptions;
+#[cfg(feature = "cli")]
+use wasmasc::size::{SizeDiff, SizeReport};
+#[cfg(feature = "cli")]
+use wasmasc::{AscPlugin, BuildConfig, OptimizationLevel, Plugin, PluginError};
 
 #[cfg(feature = "cli")]
 #[derive(Parser)]
@@ -20,25 +36,306 @@ enum Commands {
     #[command(alias = "c")]
     Compile {
         #[arg(short, long, default_value = ".", value_name = "PATH")]
-        project: String,
+        project: PathBuf,
 
         #[arg(short, long, default_value = "./dist", value_name = "DIR")]
-        output: String,
+        output: PathBuf,
 
         #[arg(long, value_enum, default_value = "release")]
         optimization: CliOptimization,
 
+        /// Build every AssemblyScript package in the enclosing workspace, in dependency order
+        #[arg(short, long)]
+        workspace: bool,
+
+        /// Install node_modules before building: auto (when missing or stale), always, never
+        #[arg(long, value_enum, default_value = "auto")]
+        install: CliInstall,
+
+        /// Install from the package manager's cache only
+        #[arg(long)]
+        offline: bool,
+
+        /// When another build holds the output directory: wait for it, or fail
+        #[arg(long, value_enum, default_value = "wait")]
+        lock: CliLock,
+
+        /// Wait at most this many seconds for another build's lock
+        #[arg(long, value_name = "SECS", conflicts_with = "lock")]
+        lock_timeout: Option<u64>,
+
+        /// Use the lockfile's asc, a pinned environment and project-relative paths
+        #[arg(long)]
+        reproducible: bool,
+
+        /// Also emit ESM bindings, index.html and a loader for the browser
+        #[arg(long)]
+        webapp: bool,
+
+        /// Also emit playground.html, a form per export for trying the module out
+        #[arg(long)]
+        playground: bool,
+
+        /// Print the build result, including artifacts and integrity hashes, as JSON
+        #[arg(long)]
+        json: bool,
+
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Build a web app, serve it on localhost and reload pages on rebuild
+    Serve {
+        #[arg(short, long, default_value = ".", value_name = "PATH")]
+        project: PathBuf,
+
+        #[arg(short, long, default_value = "./dist", value_name = "DIR")]
+        output: PathBuf,
+
+        #[arg(long, value_enum, default_value = "debug")]
+        optimization: CliOptimization,
+
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        /// Serve the first build without watching for changes
+        #[arg(long)]
+        no_watch: bool,
+
+        /// Also emit playground.html, a form per export for trying the module out
+        #[arg(long)]
+        playground: bool,
+
+        /// Install node_modules before building: auto (when missing or stale), always, never
+        #[arg(long, value_enum, default_value = "auto")]
+        install: CliInstall,
+
+        /// Install from the package manager's cache only
+        #[arg(long)]
+        offline: bool,
+
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Build the project and execute it with Node
+    #[command(alias = "r")]
+    Run {
+        #[arg(short, long, default_value = ".", value_name = "PATH")]
+        project: PathBuf,
+
+        #[arg(short, long, default_value = "./dist", value_name = "DIR")]
+        output: PathBuf,
+
+        #[arg(long, value_enum, default_value = "debug")]
+        optimization: CliOptimization,
+
+        /// Export to call (defaults to `_start` when present)
+        #[arg(short, long, value_name = "NAME")]
+        export: Option<String>,
+
+        /// Call the raw module even if ESM bindings exist
+        #[arg(long)]
+        no_bindings: bool,
+
+        /// Install node_modules before building: auto (when missing or stale), always, never
+        #[arg(long, value_enum, default_value = "auto")]
+        install: CliInstall,
+
+        /// Install from the package manager's cache only
+        #[arg(long)]
+        offline: bool,
+
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Arguments passed to the export
+        #[arg(
+            value_name = "ARGS",
+            trailing_var_arg = true,
+            allow_hyphen_values = true
+        )]
+        args: Vec<String>,
+    },
+
+    /// Diagnose the Node.js, asc and package manager setup
+    Doctor {
+        #[arg(short, long, default_value = ".", value_name = "PATH")]
+        project: PathBuf,
+
+        #[arg(short, long, default_value = "./dist", value_name = "DIR")]
+        output: PathBuf,
+
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Build twice from separate copies of the project and compare hashes
+    VerifyReproducible {
+        #[arg(short, long, default_value = ".", value_name = "PATH")]
+        project: PathBuf,
+
+        #[arg(long, value_enum, default_value = "release")]
+        optimization: CliOptimization,
+
+        #[arg(long)]
+        json: bool,
+
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Remove what previous builds wrote
+    Clean {
+        #[arg(short, long, default_value = ".", value_name = "PATH")]
+        project: PathBuf,
+
+        /// List what would be removed without removing it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Also remove the build script's outputs and the asconfig.json targets
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Create a new AssemblyScript project
+    Init {
+        #[arg(default_value = ".", value_name = "PATH")]
+        path: PathBuf,
+
+        #[arg(short, long, value_enum, default_value = "library")]
+        template: CliTemplate,
+
+        /// Package name (defaults to the directory name)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Overwrite existing files
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Run the project's as-pect or @assemblyscript/unittest tests
+    #[command(alias = "t")]
+    Test {
+        #[arg(short, long, default_value = ".", value_name = "PATH")]
+        project: PathBuf,
+
+        /// Write a JUnit XML report to FILE
+        #[arg(long, value_name = "FILE")]
+        junit: Option<PathBuf>,
+
+        #[arg(long)]
+        json: bool,
+
+        /// Install node_modules before testing: auto (when missing or stale), always, never
+        #[arg(long, value_enum, default_value = "auto")]
+        install: CliInstall,
+
+        /// Install from the package manager's cache only
+        #[arg(long)]
+        offline: bool,
+
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Arguments passed to the test runner
+        #[arg(
+            value_name = "ARGS",
+            trailing_var_arg = true,
+            allow_hyphen_values = true
+        )]
+        args: Vec<String>,
+    },
+
+    /// Build the project and call an export in the embedded interpreter
+    #[cfg(feature = "interpreter")]
+    Call {
+        #[arg(value_name = "EXPORT")]
+        export: String,
+
+        /// Numeric arguments passed to the export
+        #[arg(value_name = "ARGS", allow_hyphen_values = true)]
+        args: Vec<String>,
+
+        #[arg(short, long, default_value = ".", value_name = "PATH")]
+        project: PathBuf,
+
+        #[arg(short, long, default_value = "./dist", value_name = "DIR")]
+        output: PathBuf,
+
+        #[arg(long, value_enum, default_value = "debug")]
+        optimization: CliOptimization,
+
+        /// Call an already built module instead of building the project
+        #[arg(long, value_name = "WASM")]
+        wasm: Option<PathBuf>,
+
+        /// Install node_modules before building: auto (when missing or stale), always, never
+        #[arg(long, value_enum, default_value = "auto")]
+        install: CliInstall,
+
+        /// Install from the package manager's cache only
+        #[arg(long)]
+        offline: bool,
+
         #[arg(short, long)]
         verbose: bool,
     },
 
     CanHandle {
         #[arg(value_name = "PATH")]
-        project: String,
+        project: PathBuf,
     },
 
     CheckDeps,
 
+    /// Show imports, exports, memory and features of a compiled module
+    Inspect {
+        #[arg(value_name = "WASM")]
+        wasm: PathBuf,
+
+        /// Print the summary as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Break down module size by section, function and source origin
+    Size {
+        #[arg(value_name = "WASM")]
+        wasm: PathBuf,
+
+        /// Compare against an earlier build of the same module
+        #[arg(long, value_name = "WASM")]
+        baseline: Option<PathBuf>,
+
+        /// Number of functions to list
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Map a raw wasm stack trace back to TypeScript locations
+    Symbolicate {
+        #[arg(value_name = "WASM")]
+        wasm: PathBuf,
+
+        /// Source map path (defaults to <WASM>.map)
+        #[arg(short, long, value_name = "MAP")]
+        source_map: Option<PathBuf>,
+
+        /// File containing the trace (reads stdin when omitted)
+        #[arg(short, long, value_name = "FILE")]
+        trace: Option<PathBuf>,
+    },
+
     Info,
 }
 
@@ -61,6 +358,81 @@ impl From<CliOptimization> for OptimizationLevel {
     }
 }
 
+#[cfg(feature = "cli")]
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum CliInstall {
+    Auto,
+    Always,
+    Never,
+}
+
+#[cfg(feature = "cli")]
+impl From<CliInstall> for InstallMode {
+    fn from(mode: CliInstall) -> Self {
+        match mode {
+            CliInstall::Auto => InstallMode::Auto,
+            CliInstall::Always => InstallMode::Always,
+            CliInstall::Never => InstallMode::Never,
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum CliLock {
+    Wait,
+    Fail,
+}
+
+#[cfg(feature = "cli")]
+impl From<CliLock> for LockPolicy {
+    fn from(policy: CliLock) -> Self {
+        match policy {
+            CliLock::Wait => LockPolicy::Wait,
+            CliLock::Fail => LockPolicy::Fail,
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum CliTemplate {
+    Library,
+    Wasi,
+    Web,
+}
+
+#[cfg(feature = "cli")]
+impl From<CliTemplate> for Template {
+    fn from(template: CliTemplate) -> Self {
+        match template {
+            CliTemplate::Library => Template::Library,
+            CliTemplate::Wasi => Template::Wasi,
+            CliTemplate::Web => Template::Web,
+        }
+    }
+}
+
+/// Reports an error with its code, causes and hint, then exits with the
+/// error's exit code.
+#[cfg(feature = "cli")]
+fn fail(context: &str, error: &PluginError) -> ! {
+    eprintln!("❌ {context} [{}]: {error}", error.code());
+    let mut source = std::error::Error::source(error);
+    let message = error.to_string();
+    while let Some(cause) = source {
+        let cause_message = cause.to_string();
+        if !message.contains(&cause_message) {
+            eprintln!("   caused by: {cause_message}");
+        }
+        source = cause.source();
+    }
+    if let Some(hint) = error.hint() {
+        eprintln!("   → {hint}");
+    }
+    std::process::exit(error.exit_code());
+}
+
 #[cfg(feature = "cli")]
 fn print_header() {
     println!(
@@ -85,13 +457,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             project,
             output,
             optimization,
+            workspace,
+            install,
+            offline,
+            lock,
+            lock_timeout,
+            reproducible,
+            webapp,
+            playground,
+            json,
             verbose,
         } => {
-            if verbose {
+            if verbose && !json {
                 print_header();
                 println!("🔨 Compiling AssemblyScript project...");
-                println!("📁 Project: {project}");
-                println!("📦 Output: {output}");
+                println!("📁 Project: {}", project.display());
+                println!("📦 Output: {}", output.display());
                 println!();
             }
 
@@ -101,17 +482,362 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 optimization_level: optimization.into(),
                 verbose,
                 watch: false,
+                install: install.into(),
+                offline,
+                lock: lock_timeout.map_or(lock.into(), LockPolicy::Timeout),
+                reproducible,
+                webapp,
+                playground,
             };
 
+            if workspace {
+                let Some(root) = wasmasc::workspace::find_root(&config.project_path) else {
+                    fail(
+                        "Compilation failed",
+                        &PluginError::InvalidProjectStructure {
+                            reason: format!(
+                                "no workspace found at or above {}",
+                                config.project_path.display()
+                            ),
+                        },
+                    );
+                };
+                if verbose && !json {
+                    println!("🗂️  Workspace: {}", root.display());
+                }
+
+                match wasmasc::workspace::build_all(builder.as_ref(), &root, &config) {
+                    Ok(results) if json => {
+                        let results: std::collections::BTreeMap<_, _> =
+                            results.into_iter().collect();
+                        println!("{}", serde_json::to_string_pretty(&results)?);
+                    }
+                    Ok(results) => {
+                        println!("✅ Built {} workspace package(s)", results.len());
+                        for (name, result) in &results {
+                            println!("🎯 {name}: {}", result.wasm_path.display());
+                        }
+                    }
+                    Err(e) => fail("Compilation failed", &e),
+                }
+                return Ok(());
+            }
+
             match builder.build(&config) {
+                Ok(result) if json => println!("{}", serde_json::to_string_pretty(&result)?),
                 Ok(result) => {
                     println!("✅ Compilation completed successfully!");
-                    println!("🎯 WASM file: {}", result.wasm_path);
+                    println!("🎯 WASM file: {}", result.wasm_path.display());
+                    if let Some(opt) = &result.optimization {
+                        println!(
+                            "📉 wasm-opt: {} -> {} bytes",
+                            opt.size_before, opt.size_after
+                        );
+                    }
+                    if verbose {
+                        for (path, integrity) in &result.integrity {
+                            println!("🔒 {}: {integrity}", path.display());
+                        }
+                        if let Some(summary) = &result.module_summary {
+                            println!();
+                            print!("{summary}");
+                        }
+                    }
+                }
+                Err(e) => fail("Compilation failed", &e),
+            }
+        }
+
+        Commands::Serve {
+            project,
+            output,
+            optimization,
+            host,
+            port,
+            no_watch,
+            playground,
+            install,
+            offline,
+            verbose,
+        } => {
+            let config = BuildConfig {
+                project_path: project,
+                output_dir: output,
+                optimization_level: optimization.into(),
+                verbose,
+                watch: !no_watch,
+                install: install.into(),
+                offline,
+                lock: LockPolicy::Wait,
+                reproducible: false,
+                webapp: true,
+                playground,
+            };
+            let options = ServeOptions {
+                host,
+                port,
+                watch: !no_watch,
+            };
+            if let Err(e) = wasmasc::serve::serve(builder.as_ref(), &config, &options) {
+                fail("Serve failed", &e);
+            }
+        }
+
+        Commands::Run {
+            project,
+            output,
+            optimization,
+            export,
+            no_bindings,
+            install,
+            offline,
+            verbose,
+            args,
+        } => {
+            let config = BuildConfig {
+                project_path: project,
+                output_dir: output,
+                optimization_level: optimization.into(),
+                verbose,
+                watch: false,
+                install: install.into(),
+                offline,
+                lock: LockPolicy::Wait,
+                reproducible: false,
+                webapp: false,
+                playground: false,
+            };
+            let options = RunOptions {
+                export,
+                args,
+                node: None,
+                ignore_bindings: no_bindings,
+            };
+
+            match wasmasc::run::build_and_run(builder.as_ref(), &config, &options) {
+                Ok(outcome) => {
+                    if let Some(abort) = &outcome.abort {
+                        eprintln!("❌ Aborted: {abort}");
+                        std::process::exit(1);
+                    }
+                    if let Some(error) = &outcome.error {
+                        eprintln!("❌ Execution failed: {error}");
+                        if verbose {
+                            if let Some(stack) = &outcome.stack {
+                                eprintln!("{stack}");
+                            }
+                        }
+                        std::process::exit(1);
+                    }
+                    if let Some(value) = &outcome.value {
+                        println!("{value}");
+                    }
+                }
+                Err(e) => fail("Run failed", &e),
+            }
+        }
+
+        Commands::Doctor {
+            project,
+            output,
+            json,
+        } => {
+            let report = wasmasc::doctor::diagnose(&project, &output);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_header();
+                print!("{report}");
+                println!();
+                match report.problems().count() {
+                    0 => println!("✅ No problems found"),
+                    n => println!("Found {n} problem(s)"),
+                }
+            }
+            if report.has_errors() {
+                std::process::exit(1);
+            }
+        }
+
+        Commands::VerifyReproducible {
+            project,
+            optimization,
+            json,
+            verbose,
+        } => {
+            let mut config = BuildConfig::new(project, PathBuf::new());
+            config.optimization_level = optimization.into();
+            config.verbose = verbose;
+
+            let report = match wasmasc::reproducible::verify(builder.as_ref(), &config) {
+                Ok(report) => report,
+                Err(e) => fail("Verification failed", &e),
+            };
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{report}");
+            }
+            if !report.is_reproducible() {
+                eprintln!("❌ Builds differ");
+                std::process::exit(1);
+            }
+            if !json {
+                println!("✅ Build is reproducible");
+            }
+        }
+
+        Commands::Clean {
+            project,
+            dry_run,
+            all,
+        } => {
+            let plan = wasmasc::clean::plan(&project, all);
+            if plan.is_empty() {
+                println!("✅ Nothing to clean");
+                return Ok(());
+            }
+            for path in &plan.paths {
+                println!("   - {}", path.display());
+            }
+            if dry_run {
+                println!("Would remove {} path(s)", plan.paths.len());
+            } else {
+                if let Err(e) = plan.execute() {
+                    fail("Clean failed", &e);
+                }
+                println!("✅ Removed {} path(s)", plan.paths.len());
+            }
+        }
+
+        Commands::Init {
+            path,
+            template,
+            name,
+            force,
+        } => {
+            let options = InitOptions {
+                template: template.into(),
+                name,
+                force,
+            };
+
+            match wasmasc::scaffold::init_project(&path, &options) {
+                Ok(files) => {
+                    for file in &files {
+                        println!("   + {}", file.display());
+                    }
+                    println!("✅ Created AssemblyScript project in {}", path.display());
+                    println!();
+                    println!("Next steps:");
+                    if path != Path::new(".") {
+                        println!("   cd {}", path.display());
+                    }
+                    println!("   npm install");
+                    println!("   wasmasc compile");
+                }
+                Err(e) => fail("Init failed", &e),
+            }
+        }
+
+        Commands::Test {
+            project,
+            junit,
+            json,
+            install,
+            offline,
+            verbose,
+            args,
+        } => {
+            let config = BuildConfig {
+                project_path: project,
+                output_dir: PathBuf::new(),
+                optimization_level: OptimizationLevel::Debug,
+                verbose,
+                watch: false,
+                install: install.into(),
+                offline,
+                lock: LockPolicy::Wait,
+                reproducible: false,
+                webapp: false,
+                playground: false,
+            };
+            let options = wasmasc::test_runner::TestOptions { args };
+
+            match wasmasc::test_runner::run_tests(&config, &options) {
+                Ok(report) => {
+                    if json {
+                        // The report already carries the runner's output.
+                        println!("{}", serde_json::to_string_pretty(&report)?);
+                    } else {
+                        if verbose || report.total() == 0 {
+                            println!("{}", report.output);
+                        }
+                        println!("{report}");
+                    }
+                    if let Some(junit) = junit {
+                        if let Err(e) = report.write_junit(&junit) {
+                            fail("Failed to write JUnit report", &e);
+                        }
+                        eprintln!("📄 JUnit report written to {}", junit.display());
+                    }
+                    if !report.is_success() {
+                        eprintln!("❌ Tests failed");
+                        std::process::exit(1);
+                    }
+                    eprintln!("✅ All tests passed");
                 }
-                Err(e) => {
-                    eprintln!("❌ Compilation failed: {e}");
-                    std::process::exit(1);
+                Err(e) => fail("Test run failed", &e),
+            }
+        }
+
+        #[cfg(feature = "interpreter")]
+        Commands::Call {
+            export,
+            args,
+            project,
+            output,
+            optimization,
+            wasm,
+            install,
+            offline,
+            verbose,
+        } => {
+            let outcome = match wasm {
+                Some(wasm) => wasmasc::interpreter::call_export(&wasm, &export, &args),
+                None => {
+                    let config = BuildConfig {
+                        project_path: project,
+                        output_dir: output,
+                        optimization_level: optimization.into(),
+                        verbose,
+                        watch: false,
+                        install: install.into(),
+                        offline,
+                        lock: LockPolicy::Wait,
+                        reproducible: false,
+                        webapp: false,
+                        playground: false,
+                    };
+                    wasmasc::interpreter::build_and_call(builder.as_ref(), &config, &export, &args)
                 }
+            };
+
+            match outcome {
+                Ok(outcome) => {
+                    if let Some(abort) = &outcome.abort {
+                        eprintln!("❌ Aborted: {abort}");
+                        std::process::exit(1);
+                    }
+                    if let Some(error) = &outcome.error {
+                        eprintln!("❌ Trap: {error}");
+                        std::process::exit(1);
+                    }
+                    if let Some(value) = &outcome.value {
+                        println!("{value}");
+                    }
+                }
+                Err(e) => fail("Call failed", &e),
             }
         }
 
@@ -142,6 +868,61 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
 
+        Commands::Inspect { wasm, json } => match wasmasc::inspect::inspect_file(&wasm) {
+            Ok(summary) if json => println!("{}", serde_json::to_string_pretty(&summary)?),
+            Ok(summary) => print!("{summary}"),
+            Err(e) => fail("Inspection failed", &e),
+        },
+
+        Commands::Size {
+            wasm,
+            baseline,
+            top,
+            json,
+        } => {
+            let report = match SizeReport::from_file(&wasm) {
+                Ok(report) => report,
+                Err(e) => fail("Size analysis failed", &e),
+            };
+
+            match baseline {
+                Some(baseline) => {
+                    let before = match SizeReport::from_file(&baseline) {
+                        Ok(report) => report,
+                        Err(e) => fail("Size analysis of baseline failed", &e),
+                    };
+                    let diff = SizeDiff::between(&before, &report);
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&diff)?);
+                    } else {
+                        print!("{}", diff.display(top));
+                    }
+                }
+                None if json => println!("{}", serde_json::to_string_pretty(&report)?),
+                None => print!("{}", report.display(top)),
+            }
+        }
+
+        Commands::Symbolicate {
+            wasm,
+            source_map,
+            trace,
+        } => {
+            let trace = match trace {
+                Some(path) => std::fs::read_to_string(path)?,
+                None => {
+                    let mut buf = String::new();
+                    std::io::stdin().read_to_string(&mut buf)?;
+                    buf
+                }
+            };
+
+            match wasmasc::symbolicate::symbolicate_trace(&wasm, source_map.as_deref(), &trace) {
+                Ok(output) => print!("{output}"),
+                Err(e) => fail("Symbolication failed", &e),
+            }
+        }
+
         Commands::Info => {
             print_header();
             println!("🔧 Plugin Information");
@@ -0,0 +1,310 @@
+use crate::config::{FeaturesConfig, PrecompressConfig, ProjectConfig};
+use crate::install::InstallMode;
+use crate::wasm_opt::OptimizationReport;
+use crate::{
+    workspace, BuildConfig, BuildResult, CommandExecutor, OptimizationLevel, PluginResult,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Written next to the build's artifacts in the output directory.
+pub const MANIFEST_FILE: &str = "wasmasc-manifest.json";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactKind {
+    Wasm,
+    Wat,
+    Js,
+    #[serde(rename = "d.ts")]
+    Dts,
+    Map,
+    Html,
+    /// The export playground page.
+    Playground,
+    Other,
+}
+
+impl ArtifactKind {
+    pub fn of(path: impl AsRef<Path>) -> Self {
+        let name = path
+            .as_ref()
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name.ends_with(".d.ts") {
+            return ArtifactKind::Dts;
+        }
+        if name == crate::playground::PLAYGROUND_FILE {
+            return ArtifactKind::Playground;
+        }
+        match name.rsplit_once('.').map(|(_, ext)| ext) {
+            Some("wasm") => ArtifactKind::Wasm,
+            Some("wat") => ArtifactKind::Wat,
+            Some("js" | "mjs" | "cjs") => ArtifactKind::Js,
+            Some("map") => ArtifactKind::Map,
+            Some("html" | "htm") => ArtifactKind::Html,
+            _ => ArtifactKind::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Artifact {
+    /// Relative to the output directory.
+    pub path: PathBuf,
+    pub kind: ArtifactKind,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl Artifact {
+    pub fn from_file(path: impl AsRef<Path>, output_dir: impl AsRef<Path>) -> PluginResult<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        Ok(Self {
+            path: path.strip_prefix(output_dir).unwrap_or(path).to_path_buf(),
+            kind: ArtifactKind::of(path),
+            size: bytes.len() as u64,
+            sha256: hex(&Sha256::digest(&bytes)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Compiler {
+    /// Resolved path of the `asc` that was run, when it could be found.
+    pub path: Option<PathBuf>,
+    pub version: Option<String>,
+}
+
+impl Compiler {
+    /// Finds the `asc` a build of `project_path` runs: the project's or
+    /// workspace's `node_modules/.bin`, or `PATH` for global builds.
+    pub fn resolve(project_path: impl AsRef<Path>, global: bool) -> Self {
+        let path = if global {
+            find_on_path("asc")
+        } else {
+            workspace::find_bin(project_path, "asc")
+        }
+        .map(|path| std::path::absolute(&path).unwrap_or(path));
+        let version = path.as_deref().and_then(package_version).or_else(|| {
+            global
+                .then(|| CommandExecutor::tool_version("asc"))
+                .flatten()
+                .map(|v| v.trim_start_matches("Version ").to_string())
+        });
+        Self { path, version }
+    }
+}
+
+/// The package.json script a package-manager build ran.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Script {
+    pub package_manager: String,
+    pub name: String,
+    /// The script's body from package.json.
+    pub command: String,
+}
+
+/// Options that affected the build's output.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EffectiveOptions {
+    pub optimization_level: OptimizationLevel,
+    pub features: FeaturesConfig,
+    pub wasm_opt: Option<OptimizationReport>,
+    pub install: InstallMode,
+    pub offline: bool,
+    pub reproducible: bool,
+    #[serde(default)]
+    pub webapp: bool,
+    #[serde(default)]
+    pub playground: bool,
+    /// The `[output] name` template the artifacts were named with.
+    #[serde(default)]
+    pub output_name: Option<String>,
+    #[serde(default)]
+    pub precompress: Option<PrecompressConfig>,
+}
+
+/// What a build produced and how, for release auditing. Compile steps fill
+/// in how the compiler was run; [`BuildManifest::finish`] adds the rest once
+/// the artifacts are in place.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct BuildManifest {
+    pub wasmasc_version: String,
+    /// Seconds since the Unix epoch: `SOURCE_DATE_EPOCH` when set, else now.
+    pub timestamp: u64,
+    pub artifacts: Vec<Artifact>,
+    /// Relative to the project.
+    pub entry_files: Vec<PathBuf>,
+    pub compiler: Compiler,
+    pub script: Option<Script>,
+    /// Program and arguments, exactly as run.
+    pub command: Vec<String>,
+    pub options: Option<EffectiveOptions>,
+}
+
+impl BuildManifest {
+    /// Hashes the result's published artifacts and records the options.
+    pub fn finish(
+        &mut self,
+        config: &BuildConfig,
+        project_config: &ProjectConfig,
+        result: &BuildResult,
+    ) -> PluginResult<()> {
+        self.wasmasc_version = env!("CARGO_PKG_VERSION").to_string();
+        for entry in &mut self.entry_files {
+            if let Ok(relative) = entry.strip_prefix(&config.project_path) {
+                *entry = relative.to_path_buf();
+            }
+        }
+        self.timestamp = timestamp();
+        self.artifacts = std::iter::once(&result.wasm_path)
+            .chain(&result.js_path)
+            .chain(&result.additional_files)
+            .map(|path| Artifact::from_file(path, &config.output_dir))
+            .collect::<PluginResult<_>>()?;
+        self.options = Some(EffectiveOptions {
+            optimization_level: config.optimization_level.clone(),
+            features: project_config.features.clone(),
+            wasm_opt: result.optimization.clone(),
+            install: config.install,
+            offline: config.offline,
+            reproducible: config.reproducible,
+            webapp: config.webapp,
+            playground: config.playground,
+            output_name: project_config.output.name.clone(),
+            precompress: project_config.precompress.clone(),
+        });
+        Ok(())
+    }
+
+    /// Writes the manifest to `output_dir`, replacing any earlier one whole.
+    pub fn write(&self, output_dir: impl AsRef<Path>) -> PluginResult<PathBuf> {
+        let output_dir = output_dir.as_ref();
+        let path = output_dir.join(MANIFEST_FILE);
+        let partial = output_dir.join(format!(".{MANIFEST_FILE}.{}.partial", std::process::id()));
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::write(&partial, json + "\n")?;
+        fs::rename(&partial, &path)?;
+        Ok(path)
+    }
+
+    pub fn load(output_dir: impl AsRef<Path>) -> Option<Self> {
+        let content = fs::read_to_string(output_dir.as_ref().join(MANIFEST_FILE)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+}
+
+/// `SOURCE_DATE_EPOCH` if set and valid, otherwise the current time.
+pub fn timestamp() -> u64 {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        })
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn find_on_path(tool: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| match cfg!(windows) {
+            true => dir.join(format!("{tool}.cmd")),
+            false => dir.join(tool),
+        })
+        .find(|bin| bin.is_file())
+}
+
+/// Version of the `assemblyscript` package an `asc` shim belongs to.
+fn package_version(bin: &Path) -> Option<String> {
+    let real = fs::canonicalize(bin).ok()?;
+    real.ancestors().skip(1).find_map(|dir| {
+        let package = workspace::read_package_json(dir)?;
+        (package["name"] == "assemblyscript")
+            .then(|| package["version"].as_str().map(str::to_string))
+            .flatten()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArtifactKind, BuildManifest, EffectiveOptions, MANIFEST_FILE};
+    use crate::config::{FeaturesConfig, PrecompressConfig};
+    use crate::install::InstallMode;
+    use crate::OptimizationLevel;
+    use std::path::PathBuf;
+
+    #[test]
+    fn artifact_kinds_follow_file_names() {
+        let kinds = [
+            ("index.wasm", ArtifactKind::Wasm),
+            ("index.wat", ArtifactKind::Wat),
+            ("index.js", ArtifactKind::Js),
+            ("index.d.ts", ArtifactKind::Dts),
+            ("INDEX.D.TS", ArtifactKind::Dts),
+            ("index.wasm.map", ArtifactKind::Map),
+            ("index.html", ArtifactKind::Html),
+            ("playground.html", ArtifactKind::Playground),
+            ("nested/playground.html", ArtifactKind::Playground),
+            ("index.wasm.gz", ArtifactKind::Other),
+            ("LICENSE", ArtifactKind::Other),
+        ];
+        for (name, kind) in kinds {
+            assert_eq!(ArtifactKind::of(name), kind, "{name}");
+        }
+    }
+
+    #[test]
+    fn manifest_round_trips_through_its_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = BuildManifest {
+            wasmasc_version: "0.1.1".to_string(),
+            timestamp: 1_700_000_000,
+            entry_files: vec![PathBuf::from("assembly/index.ts")],
+            command: vec!["asc".to_string(), "assembly/index.ts".to_string()],
+            options: Some(EffectiveOptions {
+                optimization_level: OptimizationLevel::Size,
+                features: FeaturesConfig {
+                    enable: vec!["threads".to_string()],
+                    disable: Vec::new(),
+                },
+                wasm_opt: None,
+                install: InstallMode::Auto,
+                offline: false,
+                reproducible: true,
+                webapp: true,
+                playground: true,
+                output_name: Some("[name].[hash:8]".to_string()),
+                precompress: Some(PrecompressConfig::default()),
+            }),
+            ..BuildManifest::default()
+        };
+
+        let path = manifest.write(dir.path()).unwrap();
+        assert_eq!(path, dir.path().join(MANIFEST_FILE));
+        assert_eq!(BuildManifest::load(dir.path()), Some(manifest));
+    }
+
+    #[test]
+    fn options_from_older_manifests_still_load() {
+        let options: EffectiveOptions = serde_json::from_str(
+            r#"{"optimization_level":"Release","features":{"enable":[],"disable":[]},
+                "wasm_opt":null,"install":"Auto","offline":false,"reproducible":false}"#,
+        )
+        .unwrap();
+        assert!(!options.webapp && !options.playground);
+        assert_eq!(options.output_name, None);
+    }
+}
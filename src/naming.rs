@@ -0,0 +1,373 @@
+use crate::config::CONFIG_FILE_NAME;
+use crate::manifest::hex;
+use crate::{BuildResult, OptimizationLevel, PluginError, PluginResult};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// Values a name template's placeholders expand to.
+pub struct NameParts<'a> {
+    /// The artifact's own name, without extension.
+    pub name: &'a str,
+    /// Hex SHA-256 of the module as compiled.
+    pub hash: &'a str,
+    pub level: &'a OptimizationLevel,
+}
+
+/// Expands `[name]`, `[hash]`, `[hash:N]`, `[target]` and `[opt]` in a
+/// template such as `[name].[hash:8]`.
+pub fn render(template: &str, parts: &NameParts) -> PluginResult<String> {
+    let invalid = |reason: String| PluginError::InvalidConfig {
+        path: CONFIG_FILE_NAME.to_string(),
+        reason: format!("output name \"{template}\": {reason}"),
+    };
+
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('[') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(']')
+            .ok_or_else(|| invalid("unclosed '['".to_string()))?
+            + start;
+        let placeholder = &rest[start + 1..end];
+        match placeholder.split_once(':') {
+            None if placeholder == "name" => out.push_str(parts.name),
+            None if placeholder == "hash" => out.push_str(parts.hash),
+            None if placeholder == "target" => out.push_str(parts.level.asconfig_target()),
+            None if placeholder == "opt" => out.push_str(parts.level.name()),
+            Some(("hash", len)) => {
+                let len: usize = len
+                    .parse()
+                    .ok()
+                    .filter(|len| (1..=parts.hash.len()).contains(len))
+                    .ok_or_else(|| invalid(format!("invalid hash length '{len}'")))?;
+                out.push_str(&parts.hash[..len]);
+            }
+            _ => return Err(invalid(format!("unknown placeholder [{placeholder}]"))),
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+
+    if out.is_empty() || out.contains(['/', '\\']) || out.starts_with('.') {
+        return Err(invalid(format!("'{out}' is not a plain file name")));
+    }
+    Ok(out)
+}
+
+/// Renames the module and every artifact named after it (`x.wasm.map`,
+/// `x.js`, `x.d.ts`, ...) using `template`, and points the bindings, source
+/// map and the module's `sourceMappingURL` at the new names.
+///
+/// The hash is taken before the module's `sourceMappingURL` is rewritten, so
+/// it identifies the compiled code rather than the name it ends up with.
+pub fn apply(
+    result: &mut BuildResult,
+    template: &str,
+    level: &OptimizationLevel,
+) -> PluginResult<()> {
+    let wasm_name = file_name(&result.wasm_path);
+    let Some(stem) = wasm_name.strip_suffix(".wasm").map(str::to_string) else {
+        return Ok(());
+    };
+    let wasm = fs::read(&result.wasm_path)?;
+    let hash = hex(&Sha256::digest(&wasm));
+    let renamed = render(
+        template,
+        &NameParts {
+            name: &stem,
+            hash: &hash,
+            level,
+        },
+    )?;
+    if renamed == stem {
+        return Ok(());
+    }
+
+    let (old_wasm, new_wasm) = (format!("{stem}.wasm"), format!("{renamed}.wasm"));
+    if let Some(patched) =
+        rewrite_source_mapping_url(&wasm, |url| replace_file_name(url, &old_wasm, &new_wasm))
+    {
+        fs::write(&result.wasm_path, patched)?;
+    }
+
+    let paths = std::iter::once(&mut result.wasm_path)
+        .chain(result.js_path.as_mut())
+        .chain(result.additional_files.iter_mut());
+    for path in paths {
+        let name = file_name(path);
+        let Some(suffix) = name.strip_prefix(&stem).filter(|s| s.starts_with('.')) else {
+            continue;
+        };
+        if [".js", ".mjs", ".d.ts", ".wasm.map"]
+            .iter()
+            .any(|ext| suffix.ends_with(ext))
+        {
+            let content = fs::read_to_string(&*path)?;
+            let updated = replace_file_name(&content, &old_wasm, &new_wasm);
+            if updated != content {
+                fs::write(&*path, updated)?;
+            }
+        }
+        let target = path.with_file_name(format!("{renamed}{suffix}"));
+        fs::rename(&*path, &target)?;
+        *path = target;
+    }
+    Ok(())
+}
+
+/// `content` with `old` replaced by `new` wherever it starts the text or
+/// follows a quote or `/`, so renaming `index.wasm` leaves `myindex.wasm`.
+fn replace_file_name(content: &str, old: &str, new: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut last = 0;
+    for (i, _) in content.match_indices(old) {
+        let boundary = content[..i]
+            .chars()
+            .next_back()
+            .is_none_or(|c| matches!(c, '"' | '\'' | '`' | '/'));
+        if boundary {
+            out.push_str(&content[last..i]);
+            out.push_str(new);
+            last = i + old.len();
+        }
+    }
+    out.push_str(&content[last..]);
+    out
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Re-encodes the module with its `sourceMappingURL` custom section's URL
+/// passed through `f`. `None` when the module has no such section.
+fn rewrite_source_mapping_url(wasm: &[u8], f: impl Fn(&str) -> String) -> Option<Vec<u8>> {
+    let mut pos = 8;
+    while pos < wasm.len() {
+        let start = pos;
+        let id = wasm[pos];
+        let (size, len) = read_leb(&wasm[pos + 1..])?;
+        let content_start = pos + 1 + len;
+        let end = content_start.checked_add(size as usize)?;
+        let content = wasm.get(content_start..end)?;
+        pos = end;
+        if id != 0 {
+            continue;
+        }
+
+        let (name_len, n) = read_leb(content)?;
+        let name = content.get(n..n + name_len as usize)?;
+        if name != b"sourceMappingURL" {
+            continue;
+        }
+        let payload = &content[n + name_len as usize..];
+        let (url_len, m) = read_leb(payload)?;
+        let url = std::str::from_utf8(payload.get(m..m + url_len as usize)?).ok()?;
+
+        let mut section = Vec::new();
+        write_leb(&mut section, name.len() as u32);
+        section.extend_from_slice(name);
+        let url = f(url);
+        write_leb(&mut section, url.len() as u32);
+        section.extend_from_slice(url.as_bytes());
+
+        let mut out = wasm[..start].to_vec();
+        out.push(0);
+        write_leb(&mut out, section.len() as u32);
+        out.extend(section);
+        out.extend_from_slice(&wasm[end..]);
+        return Some(out);
+    }
+    None
+}
+
+fn read_leb(bytes: &[u8]) -> Option<(u32, usize)> {
+    let mut value = 0u32;
+    for (i, byte) in bytes.iter().take(5).enumerate() {
+        value |= u32::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+fn write_leb(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply, render, replace_file_name, rewrite_source_mapping_url, write_leb, NameParts,
+    };
+    use crate::{BuildResult, OptimizationLevel, PluginError};
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    const HASH: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    fn render_with(template: &str) -> Result<String, PluginError> {
+        render(
+            template,
+            &NameParts {
+                name: "index",
+                hash: HASH,
+                level: &OptimizationLevel::Size,
+            },
+        )
+    }
+
+    /// A module with an empty type section and a `sourceMappingURL` section.
+    fn module_with_map_url(url: &str) -> Vec<u8> {
+        let mut section = Vec::new();
+        write_leb(&mut section, 16);
+        section.extend_from_slice(b"sourceMappingURL");
+        write_leb(&mut section, url.len() as u32);
+        section.extend_from_slice(url.as_bytes());
+
+        let mut wasm = b"\0asm\x01\0\0\0\x01\x01\x00".to_vec();
+        wasm.push(0);
+        write_leb(&mut wasm, section.len() as u32);
+        wasm.extend(section);
+        wasm
+    }
+
+    fn map_url(wasm: &[u8]) -> Option<String> {
+        wasmparser::Parser::new(0)
+            .parse_all(wasm)
+            .find_map(|payload| match payload.unwrap() {
+                wasmparser::Payload::CustomSection(section)
+                    if section.name() == "sourceMappingURL" =>
+                {
+                    let data = section.data();
+                    Some(String::from_utf8(data[1..].to_vec()).unwrap())
+                }
+                _ => None,
+            })
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        assert_eq!(render_with("[name].[hash:8]").unwrap(), "index.01234567");
+        assert_eq!(
+            render_with("[name]-[hash]").unwrap(),
+            format!("index-{HASH}")
+        );
+        assert_eq!(
+            render_with("[name].[target].[opt]").unwrap(),
+            "index.release.size"
+        );
+        assert_eq!(render_with("[hash:64]").unwrap(), HASH);
+    }
+
+    #[test]
+    fn rejects_bad_hash_lengths_and_placeholders() {
+        for template in [
+            "[hash:0]",
+            "[hash:99]",
+            "[hash:x]",
+            "[name",
+            "[name].[bogus]",
+        ] {
+            assert!(
+                matches!(
+                    render_with(template),
+                    Err(PluginError::InvalidConfig { .. })
+                ),
+                "{template}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_names_that_are_not_plain_file_names() {
+        for template in ["dist/[name]", "[name]\\x", ".[hash:8]", ""] {
+            assert!(render_with(template).is_err(), "{template}");
+        }
+    }
+
+    #[test]
+    fn replaces_only_whole_file_names() {
+        assert_eq!(
+            replace_file_name(
+                r#"new URL("index.wasm"), "./index.wasm.map", "myindex.wasm""#,
+                "index.wasm",
+                "index.abc.wasm"
+            ),
+            r#"new URL("index.abc.wasm"), "./index.abc.wasm.map", "myindex.wasm""#
+        );
+        assert_eq!(
+            replace_file_name("index.wasm.map", "index.wasm", "x.wasm"),
+            "x.wasm.map"
+        );
+    }
+
+    #[test]
+    fn rewrites_source_mapping_url_in_place() {
+        let wasm = module_with_map_url("index.wasm.map");
+        let patched =
+            rewrite_source_mapping_url(&wasm, |url| url.replace("index", "index.v2")).unwrap();
+        wasmparser::Validator::new().validate_all(&patched).unwrap();
+        assert_eq!(map_url(&patched).as_deref(), Some("index.v2.wasm.map"));
+        assert!(rewrite_source_mapping_url(b"\0asm\x01\0\0\0", |u| u.to_string()).is_none());
+    }
+
+    #[test]
+    fn apply_renames_artifacts_and_their_references() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name);
+        fs::write(path("index.wasm"), module_with_map_url("index.wasm.map")).unwrap();
+        fs::write(path("index.wasm.map"), r#"{"file":"index.wasm"}"#).unwrap();
+        fs::write(
+            path("index.js"),
+            r#"fetch("./index.wasm"); fetch("myindex.wasm");"#,
+        )
+        .unwrap();
+        let mut result = BuildResult {
+            wasm_path: path("index.wasm"),
+            js_path: Some(path("index.js")),
+            additional_files: vec![path("index.wasm.map")],
+            is_wasm_bindgen: false,
+            module_summary: None,
+            optimization: None,
+            manifest: None,
+            integrity: BTreeMap::new(),
+        };
+
+        apply(&mut result, "[name].[hash:8]", &OptimizationLevel::Release).unwrap();
+
+        let wasm_name = result.wasm_path.file_name().unwrap().to_string_lossy();
+        let renamed = wasm_name.strip_suffix(".wasm").unwrap().to_string();
+        assert!(renamed.starts_with("index.") && renamed.len() == "index.".len() + 8);
+        assert_eq!(result.js_path, Some(path(&format!("{renamed}.js"))));
+        assert_eq!(
+            result.additional_files,
+            [path(&format!("{renamed}.wasm.map"))]
+        );
+        assert!(!path("index.wasm").exists());
+
+        let wasm = fs::read(&result.wasm_path).unwrap();
+        assert_eq!(map_url(&wasm), Some(format!("{renamed}.wasm.map")));
+        assert_eq!(
+            fs::read_to_string(&result.additional_files[0]).unwrap(),
+            format!(r#"{{"file":"{renamed}.wasm"}}"#)
+        );
+        assert_eq!(
+            fs::read_to_string(result.js_path.as_ref().unwrap()).unwrap(),
+            format!(r#"fetch("./{renamed}.wasm"); fetch("myindex.wasm");"#)
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{BuildResult, PathResolver, PluginError, PluginResult};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Advisory lock file held for the duration of a build, one per output directory.
+pub const LOCK_FILE: &str = ".wasmasc.lock";
+
+pub(crate) const STAGING_PREFIX: &str = ".wasmasc-staging-";
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LockPolicy {
+    /// Block until the other build releases the output directory.
+    #[default]
+    Wait,
+    /// Fail with `PluginError::OutputLocked`.
+    Fail,
+    /// Wait up to this many seconds, then fail with `PluginError::Timeout`.
+    Timeout(u64),
+}
+
+/// Exclusive lock on an output directory. Released when dropped, or by the
+/// OS if the process dies, so an interrupted build never leaves it stuck.
+pub struct OutputLock {
+    _file: File,
+}
+
+impl OutputLock {
+    pub fn acquire(output_dir: impl AsRef<Path>, policy: LockPolicy) -> PluginResult<Self> {
+        let output_dir = output_dir.as_ref();
+        PathResolver::ensure_output_directory(output_dir)?;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(output_dir.join(LOCK_FILE))?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut holder = String::new();
+                let _ = file.read_to_string(&mut holder);
+                let holder = match holder.trim() {
+                    "" => "another build".to_string(),
+                    pid => format!("another build (pid {pid})"),
+                };
+                match policy {
+                    LockPolicy::Fail => {
+                        return Err(PluginError::OutputLocked {
+                            path: output_dir.display().to_string(),
+                            holder,
+                        })
+                    }
+                    LockPolicy::Wait => {
+                        eprintln!(
+                            "⏳ Waiting for {holder} to release {}...",
+                            output_dir.display()
+                        );
+                        file.lock()?;
+                    }
+                    LockPolicy::Timeout(seconds) => {
+                        eprintln!(
+                            "⏳ Waiting up to {seconds}s for {holder} to release {}...",
+                            output_dir.display()
+                        );
+                        let deadline = Instant::now() + Duration::from_secs(seconds);
+                        loop {
+                            match file.try_lock() {
+                                Ok(()) => break,
+                                Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                                    thread::sleep(Duration::from_millis(100));
+                                }
+                                Err(TryLockError::WouldBlock) => {
+                                    return Err(PluginError::Timeout {
+                                        operation: format!(
+                                            "waiting for the lock on {}",
+                                            output_dir.display()
+                                        ),
+                                        seconds,
+                                    })
+                                }
+                                Err(TryLockError::Error(e)) => return Err(e.into()),
+                            }
+                        }
+                    }
+                }
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", std::process::id())?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// Scratch directory inside the output directory that a build writes into.
+/// Being on the same filesystem, finished artifacts can be renamed into
+/// place, so readers never see a partially written file. Whatever was not
+/// published is removed on drop.
+pub struct Staging {
+    dir: PathBuf,
+    output_dir: PathBuf,
+}
+
+impl Staging {
+    /// Creates the staging directory. Call while holding the [`OutputLock`]:
+    /// staging directories left behind by killed builds are cleared first.
+    pub fn create(output_dir: impl AsRef<Path>) -> PluginResult<Self> {
+        let output_dir = output_dir.as_ref();
+        for entry in fs::read_dir(output_dir)?.flatten() {
+            if entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(STAGING_PREFIX)
+            {
+                let _ = fs::remove_dir_all(entry.path());
+            }
+        }
+
+        let dir = output_dir.join(format!("{STAGING_PREFIX}{}", std::process::id()));
+        fs::create_dir_all(&dir).map_err(|source| PluginError::OutputDirectoryCreationFailed {
+            path: dir.display().to_string(),
+            source,
+        })?;
+        Ok(Self {
+            dir,
+            output_dir: output_dir.to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Moves the result's staged files into the output directory and points
+    /// the result at them. The `.wasm` goes last, so a watcher reloading on
+    /// it finds its source map and bindings already in place.
+    pub fn publish(self, result: &mut BuildResult) -> PluginResult<()> {
+        for file in &mut result.additional_files {
+            self.publish_file(file)?;
+        }
+        if let Some(js_path) = &mut result.js_path {
+            self.publish_file(js_path)?;
+        }
+        self.publish_file(&mut result.wasm_path)
+    }
+
+    /// Where a staged file ends up once published.
+    pub fn published_path(&self, path: impl AsRef<Path>) -> PathBuf {
+        let path = path.as_ref();
+        match path.strip_prefix(&self.dir) {
+            Ok(relative) => self.output_dir.join(relative),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+
+    fn publish_file(&self, path: &mut PathBuf) -> PluginResult<()> {
+        let Ok(relative) = path.strip_prefix(&self.dir) else {
+            return Ok(());
+        };
+        let target = self.output_dir.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&path, &target)?;
+        *path = target;
+        Ok(())
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LockPolicy, OutputLock, Staging, STAGING_PREFIX};
+    use crate::{BuildResult, PluginError};
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    #[test]
+    fn second_lock_fails_or_times_out_until_released() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = OutputLock::acquire(dir.path(), LockPolicy::Fail).unwrap();
+
+        assert!(matches!(
+            OutputLock::acquire(dir.path(), LockPolicy::Fail),
+            Err(PluginError::OutputLocked { .. })
+        ));
+        assert!(matches!(
+            OutputLock::acquire(dir.path(), LockPolicy::Timeout(0)),
+            Err(PluginError::Timeout { seconds: 0, .. })
+        ));
+
+        drop(lock);
+        assert!(OutputLock::acquire(dir.path(), LockPolicy::Fail).is_ok());
+    }
+
+    #[test]
+    fn create_clears_leftover_staging_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let leftover = dir.path().join(format!("{STAGING_PREFIX}1"));
+        fs::create_dir(&leftover).unwrap();
+        fs::write(leftover.join("index.wasm"), b"stale").unwrap();
+        fs::write(dir.path().join("keep.wasm"), b"").unwrap();
+
+        let staging = Staging::create(dir.path()).unwrap();
+        assert!(!leftover.exists());
+        assert!(staging.path().is_dir());
+        assert!(dir.path().join("keep.wasm").exists());
+    }
+
+    #[test]
+    fn publish_moves_staged_files_and_drops_the_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = Staging::create(dir.path()).unwrap();
+        let staging_dir = staging.path().to_path_buf();
+        fs::write(staging_dir.join("index.wasm"), b"wasm").unwrap();
+        fs::write(staging_dir.join("index.wasm.map"), b"{}").unwrap();
+        fs::write(staging_dir.join("unlisted.tmp"), b"").unwrap();
+
+        let mut result = BuildResult {
+            wasm_path: staging_dir.join("index.wasm"),
+            js_path: None,
+            additional_files: vec![staging_dir.join("index.wasm.map")],
+            is_wasm_bindgen: false,
+            module_summary: None,
+            optimization: None,
+            manifest: None,
+            integrity: BTreeMap::new(),
+        };
+        assert_eq!(
+            staging.published_path(&result.wasm_path),
+            dir.path().join("index.wasm")
+        );
+        staging.publish(&mut result).unwrap();
+
+        assert_eq!(result.wasm_path, dir.path().join("index.wasm"));
+        assert_eq!(result.additional_files, [dir.path().join("index.wasm.map")]);
+        assert_eq!(fs::read(&result.wasm_path).unwrap(), b"wasm");
+        assert!(!staging_dir.exists());
+    }
+}
@@ -0,0 +1,281 @@
+use crate::workspace;
+use crate::{CommandExecutor, PluginError, PluginResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Lock files and the package manager that owns them, in detection order.
+pub const LOCKFILES: &[(&str, &str)] = &[
+    ("bun.lock", "bun"),
+    ("bun.lockb", "bun"),
+    ("pnpm-lock.yaml", "pnpm"),
+    ("yarn.lock", "yarn"),
+    ("package-lock.json", "npm"),
+    ("npm-shrinkwrap.json", "npm"),
+];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PackageManagerKind {
+    Npm,
+    Pnpm,
+    /// Yarn 1.x.
+    YarnClassic,
+    /// Yarn 2 and later.
+    YarnBerry,
+    Bun,
+}
+
+impl PackageManagerKind {
+    /// The executable to run.
+    pub fn command(&self) -> &'static str {
+        match self {
+            PackageManagerKind::Npm => "npm",
+            PackageManagerKind::Pnpm => "pnpm",
+            PackageManagerKind::YarnClassic | PackageManagerKind::YarnBerry => "yarn",
+            PackageManagerKind::Bun => "bun",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PackageManager {
+    pub kind: PackageManagerKind,
+    pub version: Option<String>,
+    /// Why this package manager was chosen.
+    pub reason: String,
+}
+
+impl PackageManager {
+    pub fn command(&self) -> &'static str {
+        self.kind.command()
+    }
+
+    /// Whether one of this package manager's lockfiles is in `project_path`
+    /// or its workspace root.
+    pub fn has_lockfile(&self, project_path: impl AsRef<Path>) -> bool {
+        find_lockfiles(project_path)
+            .iter()
+            .any(|(_, pm)| *pm == self.command())
+    }
+
+    /// Arguments to run a `package.json` script, forwarding `extra` to it.
+    pub fn run_script_args(&self, script: &str, extra: &[String]) -> Vec<String> {
+        let mut args = vec!["run".to_string(), script.to_string()];
+        if !extra.is_empty() {
+            if self.kind == PackageManagerKind::Npm {
+                args.push("--".to_string());
+            }
+            args.extend(extra.iter().cloned());
+        }
+        args
+    }
+
+    /// Arguments to execute a binary installed in `node_modules/.bin`.
+    pub fn exec_args(&self, binary: &str, extra: &[String]) -> Vec<String> {
+        let mut args: Vec<String> = match self.kind {
+            PackageManagerKind::Npm => vec!["exec".into(), "--no".into(), binary.into()],
+            PackageManagerKind::Pnpm | PackageManagerKind::YarnBerry => {
+                vec!["exec".into(), binary.into()]
+            }
+            PackageManagerKind::YarnClassic => vec!["run".into(), binary.into()],
+            PackageManagerKind::Bun => vec!["x".into(), binary.into()],
+        };
+        if !extra.is_empty() {
+            if self.kind == PackageManagerKind::Npm {
+                args.push("--".to_string());
+            }
+            args.extend(extra.iter().cloned());
+        }
+        args
+    }
+
+    /// Arguments for a frozen-lockfile install, or a plain install when the
+    /// project has no lockfile yet.
+    pub fn install_args(
+        &self,
+        has_lockfile: bool,
+        offline: bool,
+    ) -> PluginResult<Vec<&'static str>> {
+        let mut args = match (self.kind, has_lockfile) {
+            (PackageManagerKind::Npm, true) => vec!["ci"],
+            (PackageManagerKind::YarnBerry, true) => vec!["install", "--immutable"],
+            (_, true) => vec!["install", "--frozen-lockfile"],
+            (_, false) => vec!["install"],
+        };
+
+        if offline {
+            match self.kind {
+                PackageManagerKind::Npm
+                | PackageManagerKind::Pnpm
+                | PackageManagerKind::YarnClassic => args.push("--offline"),
+                // Berry has no flag; network access is disabled through the environment.
+                PackageManagerKind::YarnBerry => {}
+                PackageManagerKind::Bun => {
+                    return Err(PluginError::DependencyInstallFailed {
+                        tool: self.command().to_string(),
+                        reason: "offline installs are not supported".to_string(),
+                    })
+                }
+            }
+        }
+
+        Ok(args)
+    }
+
+    /// Environment variables the install command needs.
+    pub fn install_env(&self, offline: bool) -> Vec<(&'static str, &'static str)> {
+        if offline && self.kind == PackageManagerKind::YarnBerry {
+            vec![("YARN_ENABLE_NETWORK", "0")]
+        } else {
+            vec![]
+        }
+    }
+}
+
+impl fmt::Display for PackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.command())?;
+        if let Some(version) = &self.version {
+            write!(f, " {version}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Chooses the package manager for `project_path`.
+///
+/// In order: the `packageManager` field of `package.json`, lockfiles, then
+/// npm or whichever package manager is installed. Yarn's major version picks
+/// between classic and Berry. A major version other than the one
+/// `packageManager` requests is warned about, not refused.
+pub fn detect(project_path: impl AsRef<Path>) -> PluginResult<PackageManager> {
+    let project_path = project_path.as_ref();
+    let root = workspace::install_root(project_path);
+
+    let field = package_manager_field(project_path).or_else(|| package_manager_field(&root));
+    if let Some(field) = field {
+        let (name, requested) = field.split_once('@').unwrap_or((field.as_str(), ""));
+        let requested = requested.split('+').next().unwrap_or_default();
+        let version = CommandExecutor::tool_version(name).ok_or_else(|| {
+            PluginError::CompileToolNotFound {
+                tool: format!(
+                    "{name} (required by packageManager \"{field}\"; run `corepack enable`)"
+                ),
+            }
+        })?;
+        let kind = kind_for(name, &version, &root).ok_or_else(|| {
+            PluginError::InvalidProjectStructure {
+                reason: format!("Unknown packageManager \"{field}\" in package.json"),
+            }
+        })?;
+        let major = |v: &str| {
+            v.trim_start_matches('v')
+                .split('.')
+                .next()
+                .map(str::to_string)
+        };
+        if !requested.is_empty() && major(requested) != major(&version) {
+            eprintln!(
+                "⚠️  packageManager requests {name} {requested}, but {name} {version} is installed"
+            );
+        }
+        let mut reason = "packageManager field in package.json".to_string();
+        if !requested.is_empty() && !version.starts_with(requested) {
+            reason.push_str(&format!(" (requests {requested}, found {version})"));
+        }
+        return Ok(PackageManager {
+            kind,
+            version: Some(version),
+            reason,
+        });
+    }
+
+    let mut skipped = Vec::new();
+    for (file, name) in find_lockfiles(project_path) {
+        match CommandExecutor::tool_version(name) {
+            Some(version) => {
+                let kind = kind_for(name, &version, &root).unwrap_or(PackageManagerKind::Npm);
+                let mut reason = format!("found {file}");
+                if !skipped.is_empty() {
+                    reason.push_str(&format!("; skipped {}", skipped.join(", ")));
+                }
+                return Ok(PackageManager {
+                    kind,
+                    version: Some(version),
+                    reason,
+                });
+            }
+            None => skipped.push(format!("{file} ({name} not installed)")),
+        }
+    }
+
+    for name in ["npm", "pnpm", "yarn", "bun"] {
+        if let Some(version) = CommandExecutor::tool_version(name) {
+            let mut reason = "no lockfile, using default".to_string();
+            if !skipped.is_empty() {
+                reason = format!("skipped {}", skipped.join(", "));
+            }
+            return Ok(PackageManager {
+                kind: kind_for(name, &version, &root).unwrap_or(PackageManagerKind::Npm),
+                version: Some(version),
+                reason,
+            });
+        }
+    }
+
+    Err(PluginError::CompileToolNotFound {
+        tool: "npm, pnpm, yarn, or bun".to_string(),
+    })
+}
+
+/// Lock files present in the project, or in its workspace root when the
+/// project has none, in detection order.
+pub fn find_lockfiles(project_path: impl AsRef<Path>) -> Vec<(&'static str, &'static str)> {
+    let project_path = project_path.as_ref();
+    let in_dir = |dir: &Path| -> Vec<(&'static str, &'static str)> {
+        LOCKFILES
+            .iter()
+            .copied()
+            .filter(|(file, _)| dir.join(file).exists())
+            .collect()
+    };
+
+    let lockfiles = in_dir(project_path);
+    if lockfiles.is_empty() {
+        in_dir(&workspace::install_root(project_path))
+    } else {
+        lockfiles
+    }
+}
+
+fn kind_for(name: &str, version: &str, project: &Path) -> Option<PackageManagerKind> {
+    Some(match name {
+        "npm" => PackageManagerKind::Npm,
+        "pnpm" => PackageManagerKind::Pnpm,
+        "bun" => PackageManagerKind::Bun,
+        "yarn" if is_yarn_berry(version, project) => PackageManagerKind::YarnBerry,
+        "yarn" => PackageManagerKind::YarnClassic,
+        _ => return None,
+    })
+}
+
+/// Yarn reports its own major version; a Berry lockfile or `.yarnrc.yml`
+/// settles it when the version can't be read.
+fn is_yarn_berry(version: &str, project: &Path) -> bool {
+    if let Some(major) = version
+        .split('.')
+        .next()
+        .and_then(|major| major.parse::<u32>().ok())
+    {
+        return major >= 2;
+    }
+    project.join(".yarnrc.yml").exists()
+        || fs::read_to_string(project.join("yarn.lock"))
+            .is_ok_and(|lockfile| lockfile.contains("__metadata:"))
+}
+
+fn package_manager_field(project: &Path) -> Option<String> {
+    let package = workspace::read_package_json(project)?;
+    package["packageManager"].as_str().map(str::to_string)
+}
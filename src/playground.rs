@@ -0,0 +1,403 @@
+use crate::inspect::ModuleSummary;
+use crate::webapp::{escape_html, instantiate_script, title};
+use crate::{BuildResult, PluginResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const PLAYGROUND_FILE: &str = "playground.html";
+
+/// The form control a parameter gets and how its value is converted.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InputKind {
+    Int,
+    BigInt,
+    Float,
+    Bool,
+    String,
+    /// Anything else, entered as JSON.
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Param {
+    pub name: String,
+    /// AssemblyScript type when the bindings declare one, else the wasm type.
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub input: InputKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExportedFunction {
+    pub name: String,
+    pub params: Vec<Param>,
+    /// `None` for functions that return nothing.
+    pub returns: Option<String>,
+}
+
+/// Exported functions declared in asc's `.d.ts` bindings, typed from the
+/// `@param`/`@returns` tags asc writes above each declaration.
+pub fn signatures_from_dts(content: &str) -> Vec<ExportedFunction> {
+    let mut functions = Vec::new();
+    let mut tags: HashMap<String, String> = HashMap::new();
+    for line in content.lines() {
+        let line = line.trim().trim_start_matches('*').trim();
+        if line.starts_with("/**") {
+            tags.clear();
+        } else if let Some(tag) = line.strip_prefix("@param ") {
+            if let Some((name, ty)) = tag.split_once(' ') {
+                tags.insert(name.to_string(), ty.trim_matches('`').to_string());
+            }
+        } else if let Some(ty) = line.strip_prefix("@returns ") {
+            tags.insert(String::new(), ty.trim_matches('`').to_string());
+        } else if let Some(declaration) = line.strip_prefix("export declare function ") {
+            if let Some(function) = parse_declaration(declaration, &tags) {
+                functions.push(function);
+            }
+            tags.clear();
+        }
+    }
+    functions
+}
+
+/// `name(a: number, b?: string): number;`
+fn parse_declaration(
+    declaration: &str,
+    tags: &HashMap<String, String>,
+) -> Option<ExportedFunction> {
+    let (name, rest) = declaration.split_once('(')?;
+    let close = rest.rfind("):")?;
+    let returns = rest[close + 2..].trim().trim_end_matches(';').trim();
+    let params = split_top_level(&rest[..close])
+        .into_iter()
+        .filter_map(|param| {
+            let (name, ts_type) = param.split_once(':')?;
+            let name = name.trim().trim_end_matches('?').to_string();
+            let ts_type = ts_type.trim();
+            let ty = tags
+                .get(&name)
+                .cloned()
+                .unwrap_or_else(|| ts_type.to_string());
+            let input = match ts_type.trim_end_matches(" | null") {
+                "number" if matches!(ty.as_str(), "f32" | "f64") => InputKind::Float,
+                "number" => InputKind::Int,
+                "bigint" => InputKind::BigInt,
+                "boolean" => InputKind::Bool,
+                "string" => InputKind::String,
+                _ => InputKind::Json,
+            };
+            Some(Param { name, ty, input })
+        })
+        .collect();
+    Some(ExportedFunction {
+        name: name.trim().to_string(),
+        params,
+        returns: (returns != "void")
+            .then(|| tags.get("").cloned().unwrap_or_else(|| returns.to_string())),
+    })
+}
+
+/// Splits on commas that aren't inside `<>`, `()`, `[]` or `{}`. The `>` of
+/// an arrow function's `=>` closes nothing.
+fn split_top_level(params: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start, mut previous) = (0i32, 0, ' ');
+    for (i, c) in params.char_indices() {
+        let arrow = c == '>' && previous == '=';
+        previous = c;
+        match c {
+            '<' | '(' | '[' | '{' => depth += 1,
+            '>' if arrow => {}
+            '>' | ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(params[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(params[start..].trim());
+    parts.retain(|p| !p.is_empty());
+    parts
+}
+
+/// Exported functions of the raw module, typed by their wasm signatures.
+pub fn signatures_from_summary(summary: &ModuleSummary) -> Vec<ExportedFunction> {
+    summary
+        .exports
+        .iter()
+        .filter(|export| export.kind == "func")
+        .filter_map(|export| {
+            let signature = export.signature.as_deref()?;
+            let (params, returns) = signature.split_once(')')?;
+            let params = params
+                .trim_start_matches('(')
+                .split(", ")
+                .filter(|p| !p.is_empty())
+                .enumerate()
+                .map(|(i, ty)| Param {
+                    name: format!("arg{i}"),
+                    ty: ty.to_string(),
+                    input: match ty {
+                        "i32" => InputKind::Int,
+                        "i64" => InputKind::BigInt,
+                        "f32" | "f64" => InputKind::Float,
+                        _ => InputKind::Json,
+                    },
+                })
+                .collect();
+            let returns = returns.trim().trim_start_matches("->").trim();
+            Some(ExportedFunction {
+                name: export.name.clone(),
+                params,
+                returns: (!returns.is_empty()).then(|| returns.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Writes a self-contained `playground.html` next to the result's module,
+/// with a form per exported function, and adds it to `additional_files`.
+///
+/// Signatures come from the `.d.ts` bindings when the build has them, so
+/// strings and booleans go through the bindings; otherwise from the module
+/// itself, which only takes numbers.
+pub fn generate(result: &mut BuildResult, project_path: impl AsRef<Path>) -> PluginResult<PathBuf> {
+    let dts = std::iter::once(&result.wasm_path)
+        .chain(&result.additional_files)
+        .find(|path| path.to_string_lossy().ends_with(".d.ts"))
+        .and_then(|path| fs::read_to_string(path).ok());
+    let functions = match (&dts, &result.module_summary) {
+        (Some(dts), _) if result.js_path.is_some() => signatures_from_dts(dts),
+        (_, Some(summary)) => signatures_from_summary(summary),
+        _ => Vec::new(),
+    };
+
+    let spec = serde_json::to_string(&functions)
+        .map_err(std::io::Error::other)?
+        .replace("</", "<\\/");
+    let page = PAGE
+        .replace("{title}", &escape_html(&title(project_path.as_ref())))
+        .replace("{spec}", &spec)
+        .replace("{instantiate}", &instantiate_script(result));
+
+    let dir = result.wasm_path.parent().unwrap_or(Path::new("."));
+    let path = dir.join(PLAYGROUND_FILE);
+    fs::write(&path, page)?;
+    result.additional_files.push(path.clone());
+    Ok(path)
+}
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <meta name="viewport" content="width=device-width, initial-scale=1">
+  <title>{title} playground</title>
+  <style>
+    body { font: 15px/1.4 system-ui, sans-serif; max-width: 52rem; margin: 2rem auto; padding: 0 1rem; }
+    form { border: 1px solid #ccc; border-radius: 6px; padding: 1rem; margin: 1rem 0; }
+    h2 { font: 600 1rem ui-monospace, monospace; margin: 0 0 .75rem; }
+    label { display: block; margin: .25rem 0; }
+    label span { display: inline-block; min-width: 10rem; font-family: ui-monospace, monospace; }
+    pre { background: #f5f5f5; padding: .5rem; white-space: pre-wrap; margin: .5rem 0 0; }
+    pre:empty { display: none; }
+    .trace { color: #555; }
+    .abort { color: #b00020; }
+  </style>
+</head>
+<body>
+  <h1>{title}</h1>
+  <main id="functions"><p>Loading…</p></main>
+  <script type="application/json" id="spec">{spec}</script>
+  <script type="module">
+{instantiate}
+const functions = JSON.parse(document.getElementById("spec").textContent);
+const main = document.getElementById("functions");
+const element = (tag, props = {}, ...children) => {
+  const node = Object.assign(document.createElement(tag), props);
+  node.append(...children);
+  return node;
+};
+
+const convert = (input, kind) => {
+  switch (kind) {
+    case "int": case "float": return Number(input.value);
+    case "bigint": return BigInt(input.value || 0);
+    case "bool": return bindingsUrl ? input.checked : Number(input.checked);
+    case "string": return input.value;
+    default: return input.value ? JSON.parse(input.value) : null;
+  }
+};
+
+const display = (value) =>
+  value === undefined ? "(void)" : typeof value === "bigint" ? `${value}n` : JSON.stringify(value) ?? String(value);
+
+const call = (exports, fn, inputs) => {
+  const trace = [];
+  const log = console.log;
+  console.log = (...args) => {
+    trace.push(args.join(" "));
+    log(...args);
+  };
+  try {
+    const args = fn.params.map((param, i) => convert(inputs[i], param.input));
+    return { result: display(exports[fn.name](...args)), trace };
+  } catch (e) {
+    const match = /^(.*) in (.*)\((\d+):(\d+)\)$/.exec(e?.message ?? "");
+    const abort = e instanceof Abort ? e.message : match ? `${match[1]} at ${match[2]}:${match[3]}:${match[4]}` : null;
+    return abort ? { abort, trace } : { abort: String(e), trace };
+  } finally {
+    console.log = log;
+  }
+};
+
+try {
+  const exports = await instantiate();
+  const forms = functions.filter((fn) => typeof exports[fn.name] === "function").map((fn) => {
+    const inputs = fn.params.map((param) =>
+      element("input", param.input === "bool"
+        ? { type: "checkbox" }
+        : { type: ["int", "float"].includes(param.input) ? "number" : "text", step: "any",
+            placeholder: param.input === "json" ? "JSON" : param.type }));
+    const result = element("pre"), trace = element("pre", { className: "trace" }), abort = element("pre", { className: "abort" });
+    const form = element("form", {},
+      element("h2", { textContent: `${fn.name}(${fn.params.map((p) => `${p.name}: ${p.type}`).join(", ")})${fn.returns ? `: ${fn.returns}` : ""}` }),
+      ...fn.params.map((param, i) => element("label", {}, element("span", { textContent: `${param.name}: ${param.type}` }), inputs[i])),
+      element("button", { textContent: "Call" }), result, trace, abort);
+    form.addEventListener("submit", (event) => {
+      event.preventDefault();
+      const outcome = call(exports, fn, inputs);
+      result.textContent = outcome.result ?? "";
+      trace.textContent = outcome.trace.join("\n");
+      abort.textContent = outcome.abort ? `abort: ${outcome.abort}` : "";
+    });
+    return form;
+  });
+  main.replaceChildren(...(forms.length ? forms : [element("p", { textContent: "No exported functions." })]));
+} catch (e) {
+  main.replaceChildren(element("pre", { className: "abort", textContent: String(e) }));
+  throw e;
+}
+  </script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::{signatures_from_dts, signatures_from_summary, split_top_level, InputKind};
+    use crate::inspect::{ExportInfo, ModuleSummary};
+
+    const DTS: &str = r#"/** Exported memory */
+export declare const memory: WebAssembly.Memory;
+/**
+ * assembly/index/scale
+ * @param x `f32`
+ * @param factor `i32`
+ * @returns `f32`
+ */
+export declare function scale(x: number, factor?: number): number;
+/**
+ * assembly/index/greet
+ * @param name `~lib/string/String | null`
+ * @param loud `bool`
+ */
+export declare function greet(name?: string | null, loud?: boolean): void;
+/**
+ * assembly/index/sum
+ * @param values `~lib/array/Array<i32>`
+ * @param lookup `~lib/map/Map<~lib/string/String,~lib/array/Array<i64>>`
+ * @returns `i64`
+ */
+export declare function sum(values: Array<number>, lookup: Map<string, Array<bigint>>): bigint;
+"#;
+
+    #[test]
+    fn dts_signatures_take_types_from_tags() {
+        let functions = signatures_from_dts(DTS);
+        let names: Vec<_> = functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["scale", "greet", "sum"]);
+
+        let scale = &functions[0];
+        assert_eq!(scale.params[0].ty, "f32");
+        assert_eq!(scale.params[0].input, InputKind::Float);
+        assert_eq!(scale.params[1].name, "factor");
+        assert_eq!(scale.params[1].input, InputKind::Int);
+        assert_eq!(scale.returns.as_deref(), Some("f32"));
+    }
+
+    #[test]
+    fn optional_and_nullable_params_keep_their_kind() {
+        let greet = &signatures_from_dts(DTS)[1];
+        assert_eq!(greet.params[0].name, "name");
+        assert_eq!(greet.params[0].input, InputKind::String);
+        assert_eq!(greet.params[1].input, InputKind::Bool);
+        assert_eq!(greet.returns, None);
+    }
+
+    #[test]
+    fn generic_params_are_not_split_and_fall_back_to_json() {
+        let sum = &signatures_from_dts(DTS)[2];
+        assert_eq!(sum.params.len(), 2);
+        assert_eq!(sum.params[0].input, InputKind::Json);
+        assert_eq!(
+            sum.params[1].ty,
+            "~lib/map/Map<~lib/string/String,~lib/array/Array<i64>>"
+        );
+        assert_eq!(sum.params[1].input, InputKind::Json);
+        assert_eq!(sum.returns.as_deref(), Some("i64"));
+    }
+
+    #[test]
+    fn untagged_declarations_use_typescript_types() {
+        let functions = signatures_from_dts("export declare function f(a: bigint): string;");
+        assert_eq!(functions[0].params[0].ty, "bigint");
+        assert_eq!(functions[0].params[0].input, InputKind::BigInt);
+        assert_eq!(functions[0].returns.as_deref(), Some("string"));
+    }
+
+    #[test]
+    fn splits_only_on_top_level_commas() {
+        assert_eq!(
+            split_top_level(
+                "a: Map<string, Array<i32>>, b: (x: i32, y: i32) => void, c: [i32, i32]"
+            ),
+            [
+                "a: Map<string, Array<i32>>",
+                "b: (x: i32, y: i32) => void",
+                "c: [i32, i32]"
+            ]
+        );
+        assert!(split_top_level("  ").is_empty());
+    }
+
+    #[test]
+    fn summary_signatures_use_wasm_types() {
+        let export = |name: &str, kind: &str, signature: Option<&str>| ExportInfo {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            index: 0,
+            signature: signature.map(str::to_string),
+        };
+        let summary = ModuleSummary {
+            exports: vec![
+                export("add", "func", Some("(i32, i64) -> f64")),
+                export("tick", "func", Some("()")),
+                export("memory", "memory", None),
+            ],
+            ..ModuleSummary::default()
+        };
+        let functions = signatures_from_summary(&summary);
+        assert_eq!(functions.len(), 2);
+        let kinds: Vec<_> = functions[0].params.iter().map(|p| p.input).collect();
+        assert_eq!(kinds, [InputKind::Int, InputKind::BigInt]);
+        assert_eq!(functions[0].params[1].name, "arg1");
+        assert_eq!(functions[0].returns.as_deref(), Some("f64"));
+        assert!(functions[1].params.is_empty());
+        assert_eq!(functions[1].returns, None);
+    }
+}
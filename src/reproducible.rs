@@ -0,0 +1,525 @@
+use crate::manifest::BuildManifest;
+use crate::{workspace, BuildConfig, PluginError, PluginResult, WasmBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Text lockfiles that can pin the compiler, in the order they're consulted.
+const LOCKFILES: &[&str] = &[
+    "package-lock.json",
+    "pnpm-lock.yaml",
+    "yarn.lock",
+    "bun.lock",
+];
+
+/// Locale and timezone variables that can change what a compiler emits.
+const CLEARED_ENV: &[&str] = &["LANG", "LANGUAGE", "TZ", "NODE_OPTIONS"];
+
+/// Pins the environment a reproducible build's child processes see.
+pub fn pin_environment(command: &mut Command) {
+    for (key, _) in std::env::vars_os() {
+        if key.to_string_lossy().starts_with("LC_") {
+            command.env_remove(&key);
+        }
+    }
+    for key in CLEARED_ENV {
+        command.env_remove(key);
+    }
+    command.env("LC_ALL", "C").env("TZ", "UTC");
+}
+
+/// Checks that the project's `asc` is the version its lockfile pins, and
+/// returns its path. Reproducible builds never use a global compiler.
+pub fn pinned_compiler(project_path: impl AsRef<Path>) -> PluginResult<PathBuf> {
+    let project_path = project_path.as_ref();
+    let Some((lockfile, locked)) = locked_version(project_path, "assemblyscript") else {
+        return Err(PluginError::InvalidProjectStructure {
+            reason: "reproducible builds need assemblyscript pinned in a lockfile \
+                     (package-lock.json, pnpm-lock.yaml, yarn.lock or bun.lock)"
+                .to_string(),
+        });
+    };
+
+    let root = workspace::install_root(project_path);
+    let installed = [project_path, root.as_path()].iter().find_map(|dir| {
+        let package = workspace::read_package_json(dir.join("node_modules/assemblyscript"))?;
+        package["version"].as_str().map(str::to_string)
+    });
+    let asc = workspace::find_bin(project_path, "asc");
+    let (Some(installed), Some(asc)) = (installed, asc) else {
+        return Err(PluginError::CompileToolNotFound {
+            tool: format!("asc {locked} (pinned by {lockfile}) in node_modules"),
+        });
+    };
+    if installed != locked {
+        return Err(PluginError::ToolVersionMismatch {
+            tool: "assemblyscript".to_string(),
+            required: format!("{locked} (pinned by {lockfile})"),
+            found: installed,
+        });
+    }
+    Ok(asc)
+}
+
+/// Version of `package` recorded in the project's or workspace's lockfile,
+/// with the lockfile's name. Bun's binary `bun.lockb` can't be read.
+pub fn locked_version(project_path: impl AsRef<Path>, package: &str) -> Option<(String, String)> {
+    let project_path = project_path.as_ref();
+    let root = workspace::install_root(project_path);
+    [project_path, root.as_path()].iter().find_map(|dir| {
+        LOCKFILES.iter().find_map(|file| {
+            let content = fs::read_to_string(dir.join(file)).ok()?;
+            let version = match *file {
+                "package-lock.json" => npm_locked(&content, package),
+                "pnpm-lock.yaml" => pnpm_locked(&content, package),
+                "yarn.lock" => yarn_locked(&content, package),
+                _ => bun_locked(&content, package),
+            }?;
+            Some((file.to_string(), version))
+        })
+    })
+}
+
+fn npm_locked(content: &str, package: &str) -> Option<String> {
+    let lock: serde_json::Value = serde_json::from_str(content).ok()?;
+    lock["packages"][format!("node_modules/{package}")]["version"]
+        .as_str()
+        .or_else(|| lock["dependencies"][package]["version"].as_str())
+        .map(str::to_string)
+}
+
+/// Matches package keys from every lockfile version: `/name/1.0.0:` (v5),
+/// `/name@1.0.0:` (v6) and `name@1.0.0:` (v9).
+fn pnpm_locked(content: &str, package: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let key = line.trim().trim_matches(['\'', '"']);
+        let key = key
+            .strip_suffix(':')?
+            .trim_matches(['\'', '"'])
+            .trim_start_matches('/');
+        let version = key
+            .strip_prefix(package)?
+            .strip_prefix(['@', '/'])?
+            .split(['(', '_'])
+            .next()?;
+        version
+            .starts_with(|c: char| c.is_ascii_digit())
+            .then(|| version.to_string())
+    })
+}
+
+/// Classic entries read `version "1.0.0"`, Berry entries `version: 1.0.0`.
+fn yarn_locked(content: &str, package: &str) -> Option<String> {
+    let mut in_entry = false;
+    for line in content.lines() {
+        if !line.starts_with(' ') {
+            in_entry = line
+                .split(", ")
+                .any(|spec| spec.trim_matches('"').starts_with(&format!("{package}@")));
+            continue;
+        }
+        if in_entry {
+            if let Some(version) = line.trim().strip_prefix("version") {
+                return Some(
+                    version
+                        .trim_start_matches(':')
+                        .trim()
+                        .trim_matches('"')
+                        .to_string(),
+                );
+            }
+        }
+    }
+    None
+}
+
+/// `"name": ["name@1.0.0", ...]` in the text lockfile.
+fn bun_locked(content: &str, package: &str) -> Option<String> {
+    let needle = format!("\"{package}@");
+    content.lines().find_map(|line| {
+        let line = line.trim();
+        if !line.starts_with(&format!("\"{package}\": [")) {
+            return None;
+        }
+        let start = line.find(&needle)? + needle.len();
+        let version = &line[start..];
+        Some(version[..version.find('"')?].to_string())
+    })
+}
+
+/// Makes a source map's paths relative to the project and drops anything
+/// that depends on where the build ran. Returns whether the file changed.
+pub fn normalize_source_map(
+    map_path: impl AsRef<Path>,
+    project_path: impl AsRef<Path>,
+) -> PluginResult<bool> {
+    let map_path = map_path.as_ref();
+    let project_path = project_path.as_ref();
+    let roots = [
+        std::path::absolute(project_path)?,
+        fs::canonicalize(project_path)?,
+    ];
+    let content = fs::read_to_string(map_path)?;
+    let mut map: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| PluginError::InvalidSourceMap {
+            reason: format!("{}: {e}", map_path.display()),
+        })?;
+    let original = map.clone();
+
+    if let Some(sources) = map["sources"].as_array_mut() {
+        for source in sources {
+            if let Some(path) = source.as_str().map(Path::new) {
+                if let Some(relative) = roots.iter().find_map(|r| path.strip_prefix(r).ok()) {
+                    *source = relative.to_string_lossy().replace('\\', "/").into();
+                }
+            }
+        }
+    }
+    if let Some(object) = map.as_object_mut() {
+        if object
+            .get("sourceRoot")
+            .and_then(|root| root.as_str())
+            .is_some_and(|root| Path::new(root).is_absolute())
+        {
+            object.remove("sourceRoot");
+        }
+        if let Some(file) = object.get("file").and_then(|f| f.as_str()) {
+            let name = Path::new(file)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned());
+            if let Some(name) = name {
+                object.insert("file".to_string(), name.into());
+            }
+        }
+    }
+
+    if map == original {
+        return Ok(false);
+    }
+    let json = serde_json::to_string(&map).map_err(std::io::Error::other)?;
+    fs::write(map_path, json)?;
+    Ok(true)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactComparison {
+    pub path: PathBuf,
+    pub first: Option<String>,
+    pub second: Option<String>,
+}
+
+impl ArtifactComparison {
+    pub fn matches(&self) -> bool {
+        self.first.is_some() && self.first == self.second
+    }
+}
+
+/// SHA-256 of every artifact from two reproducible builds of one project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyReport {
+    pub artifacts: Vec<ArtifactComparison>,
+}
+
+impl VerifyReport {
+    pub fn is_reproducible(&self) -> bool {
+        !self.artifacts.is_empty() && self.artifacts.iter().all(|a| a.matches())
+    }
+}
+
+impl fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for artifact in &self.artifacts {
+            let short = |hash: &Option<String>| {
+                hash.as_deref()
+                    .map(|h| h[..h.len().min(16)].to_string())
+                    .unwrap_or_else(|| "missing".to_string())
+            };
+            if artifact.matches() {
+                writeln!(
+                    f,
+                    "✅ {} {}",
+                    artifact.path.display(),
+                    short(&artifact.first)
+                )?;
+            } else {
+                writeln!(
+                    f,
+                    "❌ {} {} != {}",
+                    artifact.path.display(),
+                    short(&artifact.first),
+                    short(&artifact.second)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds the project twice in reproducible mode, each time from a copy in
+/// its own temporary directory, and compares the artifacts' hashes.
+///
+/// The copies share the project's `node_modules` through a symlink (a copy
+/// elsewhere than Unix), so dependencies must already be installed. A
+/// workspace member is copied below a stand-in for its workspace root that
+/// links the root's `node_modules` and lockfile the same way.
+pub fn verify(builder: &dyn WasmBuilder, config: &BuildConfig) -> PluginResult<VerifyReport> {
+    let scratch = std::env::temp_dir().join(format!("wasmasc-verify-{}", std::process::id()));
+    let _cleanup = RemoveOnDrop(scratch.clone());
+
+    let mut builds = Vec::new();
+    for run in ["a", "b"] {
+        let project = copy_for_build(
+            &config.project_path,
+            &scratch.join(run).join("project"),
+            &config.output_dir,
+        )?;
+        let run_config = BuildConfig {
+            project_path: project,
+            output_dir: scratch.join(run).join("out"),
+            install: crate::install::InstallMode::Never,
+            reproducible: true,
+            ..config.clone()
+        };
+        let result = builder.build(&run_config)?;
+        let manifest = match result.manifest {
+            Some(manifest) => manifest,
+            None => BuildManifest::load(&run_config.output_dir).unwrap_or_default(),
+        };
+        builds.push(
+            manifest
+                .artifacts
+                .into_iter()
+                .map(|a| (a.path, a.sha256))
+                .collect::<BTreeMap<_, _>>(),
+        );
+    }
+
+    let (second, first) = (
+        builds.pop().unwrap_or_default(),
+        builds.pop().unwrap_or_default(),
+    );
+    let mut paths: Vec<&PathBuf> = first.keys().chain(second.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    Ok(VerifyReport {
+        artifacts: paths
+            .into_iter()
+            .map(|path| ArtifactComparison {
+                path: path.clone(),
+                first: first.get(path).cloned(),
+                second: second.get(path).cloned(),
+            })
+            .collect(),
+    })
+}
+
+struct RemoveOnDrop(PathBuf);
+
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Copies the project to `to` and returns the copy's path. A workspace
+/// member lands at its path relative to the workspace root, below a copy of
+/// the root's workspace manifests and links to its `node_modules` and
+/// lockfiles, so the copy resolves the same install root.
+fn copy_for_build(project_path: &Path, to: &Path, output_dir: &Path) -> PluginResult<PathBuf> {
+    let project_path = fs::canonicalize(project_path)?;
+    let root = workspace::install_root(&project_path);
+    let relative = project_path.strip_prefix(&root).unwrap_or(Path::new(""));
+    if !relative.as_os_str().is_empty() {
+        fs::create_dir_all(to)?;
+        for file in ["package.json", "pnpm-workspace.yaml"] {
+            if root.join(file).is_file() {
+                fs::copy(root.join(file), to.join(file))?;
+            }
+        }
+        for name in LOCKFILES.iter().chain(&["node_modules"]) {
+            if root.join(name).exists() {
+                link_or_copy(&root.join(name), &to.join(name))?;
+            }
+        }
+    }
+    let project = match relative.as_os_str().is_empty() {
+        true => to.to_path_buf(),
+        false => to.join(relative),
+    };
+    copy_project(&project_path, &project, output_dir)?;
+    Ok(project)
+}
+
+/// Copies the project's sources, leaving out version control, wasmasc's own
+/// state, the output directory and `node_modules`, which is linked instead.
+fn copy_project(from: &Path, to: &Path, output_dir: &Path) -> PluginResult<()> {
+    let output_dir = match output_dir.as_os_str().is_empty() {
+        true => PathBuf::new(),
+        false => fs::canonicalize(output_dir).or_else(|_| std::path::absolute(output_dir))?,
+    };
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)?.flatten() {
+        let name = entry.file_name();
+        let source = entry.path();
+        let target = to.join(&name);
+        if name == ".git"
+            || name == ".wasmasc"
+            || fs::canonicalize(&source).is_ok_and(|path| path == output_dir)
+        {
+            continue;
+        }
+        if name == "node_modules" {
+            link_or_copy(&std::path::absolute(&source)?, &target)?;
+        } else if entry.file_type()?.is_dir() {
+            copy_project(&source, &target, &output_dir)?;
+        } else {
+            fs::copy(&source, &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn link_or_copy(source: &Path, target: &Path) -> PluginResult<()> {
+    std::os::unix::fs::symlink(source, target)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn link_or_copy(source: &Path, target: &Path) -> PluginResult<()> {
+    if source.is_file() {
+        fs::copy(source, target)?;
+        return Ok(());
+    }
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)?.flatten() {
+        let target = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            link_or_copy(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bun_locked, copy_for_build, npm_locked, pinned_compiler, pnpm_locked, yarn_locked,
+    };
+    use std::fs;
+    use std::path::Path;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn npm_lockfile_reads_packages_and_legacy_dependencies() {
+        let v3 = r#"{"packages": {"": {}, "node_modules/assemblyscript": {"version": "0.27.1"}}}"#;
+        let v1 = r#"{"dependencies": {"assemblyscript": {"version": "0.19.23"}}}"#;
+        assert_eq!(npm_locked(v3, "assemblyscript").as_deref(), Some("0.27.1"));
+        assert_eq!(npm_locked(v1, "assemblyscript").as_deref(), Some("0.19.23"));
+        assert_eq!(npm_locked(v3, "binaryen"), None);
+        assert_eq!(npm_locked("not json", "assemblyscript"), None);
+    }
+
+    #[test]
+    fn pnpm_lockfile_reads_every_key_format() {
+        for key in [
+            "  /assemblyscript/0.27.1:",
+            "  /assemblyscript@0.27.1:",
+            "  assemblyscript@0.27.1:",
+            "  '/assemblyscript@0.27.1(typescript@5.4.0)':",
+        ] {
+            let content = format!("packages:\n\n  assemblyscript-loader@1.0.0:\n{key}\n");
+            assert_eq!(
+                pnpm_locked(&content, "assemblyscript").as_deref(),
+                Some("0.27.1"),
+                "{key}"
+            );
+        }
+        let specifier = "importers:\n  .:\n    devDependencies:\n      assemblyscript:\n";
+        assert_eq!(pnpm_locked(specifier, "assemblyscript"), None);
+    }
+
+    #[test]
+    fn yarn_lockfile_reads_classic_and_berry_entries() {
+        let classic = "\
+assemblyscript-loader@^1.0.0:
+  version \"1.0.0\"
+
+assemblyscript@^0.27.0, assemblyscript@~0.27.1:
+  version \"0.27.1\"
+";
+        let berry = "\
+\"assemblyscript@npm:^0.27.0\":
+  version: 0.27.2
+  resolution: \"assemblyscript@npm:0.27.2\"
+";
+        assert_eq!(
+            yarn_locked(classic, "assemblyscript").as_deref(),
+            Some("0.27.1")
+        );
+        assert_eq!(
+            yarn_locked(berry, "assemblyscript").as_deref(),
+            Some("0.27.2")
+        );
+        assert_eq!(yarn_locked(classic, "binaryen"), None);
+    }
+
+    #[test]
+    fn bun_lockfile_reads_the_package_entry() {
+        let content = r#"{
+  "lockfileVersion": 1,
+  "packages": {
+    "assemblyscript-loader": ["assemblyscript-loader@1.0.0", "", {}, "sha512-a"],
+    "assemblyscript": ["assemblyscript@0.27.3", "", {"bin": {"asc": "bin/asc.js"}}, "sha512-b"],
+  }
+}"#;
+        assert_eq!(
+            bun_locked(content, "assemblyscript").as_deref(),
+            Some("0.27.3")
+        );
+        assert_eq!(bun_locked(content, "binaryen"), None);
+    }
+
+    #[test]
+    fn workspace_member_copy_keeps_the_root_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("repo");
+        write(
+            &root.join("package.json"),
+            r#"{"workspaces": ["packages/*"]}"#,
+        );
+        write(
+            &root.join("package-lock.json"),
+            r#"{"packages": {"node_modules/assemblyscript": {"version": "0.27.1"}}}"#,
+        );
+        write(
+            &root.join("node_modules/assemblyscript/package.json"),
+            r#"{"version": "0.27.1"}"#,
+        );
+        write(&root.join("node_modules/.bin/asc"), "");
+        let member = root.join("packages/app");
+        write(&member.join("package.json"), r#"{"name": "app"}"#);
+        write(
+            &member.join("assembly/index.ts"),
+            "export function f(): void {}",
+        );
+
+        let to = dir.path().join("copy");
+        let project = copy_for_build(&member, &to, Path::new("")).unwrap();
+
+        assert_eq!(project, to.join("packages/app"));
+        assert!(project.join("assembly/index.ts").is_file());
+        assert!(!to.join("packages/other").exists());
+        let asc = pinned_compiler(&project).unwrap();
+        assert!(asc.starts_with(fs::canonicalize(&to).unwrap()));
+    }
+}
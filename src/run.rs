@@ -0,0 +1,278 @@
+use crate::inspect;
+use crate::{BuildConfig, BuildResult, CommandExecutor, PluginError, PluginResult, WasmBuilder};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fmt;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Marks the line the loader uses to report its outcome on stdout.
+const RESULT_MARKER: &str = "__WASMASC_RESULT__ ";
+
+/// Node ESM loader. Arguments: wasm path, bindings path (or empty), export
+/// name (or empty), JSON-encoded parameter types, then the call arguments.
+const NODE_LOADER: &str = r#"
+import { readFile } from "node:fs/promises";
+import { pathToFileURL } from "node:url";
+
+const [wasmPath, bindingsPath, exportName, paramTypesJson, ...rawArgs] = process.argv.slice(1);
+const paramTypes = JSON.parse(paramTypesJson);
+const report = (outcome) => console.log("__WASMASC_RESULT__ " + JSON.stringify(outcome));
+
+class Abort extends Error {
+  constructor(message, file, line, column) {
+    super(message ?? "abort");
+    Object.assign(this, { file, line, column });
+  }
+}
+
+let memory;
+const liftString = (ptr) => {
+  if (!ptr || !memory) return null;
+  const end = (ptr + new Uint32Array(memory.buffer)[(ptr - 4) >>> 2]) >>> 1;
+  const units = new Uint16Array(memory.buffer);
+  let start = ptr >>> 1, out = "";
+  while (end - start > 1024) out += String.fromCharCode(...units.subarray(start, (start += 1024)));
+  return out + String.fromCharCode(...units.subarray(start, end));
+};
+
+const convert = (value, type) => {
+  switch (type) {
+    case "i64": return BigInt(value);
+    case "i32": case "f32": case "f64": return Number(value);
+    default: return value;
+  }
+};
+
+const display = (value) =>
+  value === undefined ? null : typeof value === "bigint" ? value.toString() : JSON.stringify(value) ?? String(value);
+
+try {
+  let exports;
+  if (bindingsPath) {
+    exports = await import(pathToFileURL(bindingsPath).href);
+  } else {
+    const module = await WebAssembly.compile(await readFile(wasmPath));
+    const env = {
+      abort(msg, file, line, column) {
+        throw new Abort(liftString(msg), liftString(file), line, column);
+      },
+      trace(msg, n, ...args) {
+        console.log(`trace: ${liftString(msg)}${n ? " " + args.slice(0, n).join(", ") : ""}`);
+      },
+      seed: () => Date.now(),
+    };
+    const imports = { env };
+    let wasi;
+    for (const imp of WebAssembly.Module.imports(module)) {
+      if (imp.module === "wasi_snapshot_preview1" && !wasi) {
+        const { WASI } = await import("node:wasi");
+        wasi = new WASI({ version: "preview1", args: [wasmPath, ...rawArgs], env: process.env });
+        imports.wasi_snapshot_preview1 = wasi.wasiImport;
+      }
+      imports[imp.module] ??= {};
+      if (imp.kind === "function" && !(imp.name in imports[imp.module])) {
+        imports[imp.module][imp.name] = () => {
+          throw new Error(`unresolved import ${imp.module}.${imp.name}`);
+        };
+      }
+    }
+    const instance = await WebAssembly.instantiate(module, imports);
+    exports = instance.exports;
+    memory = exports.memory;
+    if (wasi && !exportName && exports._start) {
+      report({ value: display(wasi.start(instance)) });
+      process.exit(0);
+    }
+  }
+
+  const target = exportName || (exports._start ? "_start" : "");
+  if (!target) {
+    report({ value: null });
+  } else if (typeof exports[target] !== "function") {
+    report({ error: `export '${target}' is not a function` });
+  } else {
+    const args = rawArgs.map((arg, i) => convert(arg, paramTypes[i]));
+    report({ value: display(exports[target](...args)) });
+  }
+} catch (e) {
+  if (e instanceof Abort) {
+    report({ abort: { message: e.message, file: e.file, line: e.line, column: e.column } });
+  } else {
+    const match = /^(.*) in (.*)\((\d+):(\d+)\)$/.exec(e?.message ?? "");
+    if (match && bindingsPath) {
+      report({ abort: { message: match[1], file: match[2], line: +match[3], column: +match[4] } });
+    } else {
+      report({ error: String(e), stack: e?.stack ?? null });
+    }
+  }
+}
+"#;
+
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Export to call; `_start` is used when omitted and the module has one.
+    pub export: Option<String>,
+    pub args: Vec<String>,
+    /// Node binary to use instead of `node` on `PATH`.
+    pub node: Option<String>,
+    /// Call the raw module even when ESM bindings were generated.
+    pub ignore_bindings: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AbortInfo {
+    pub message: Option<String>,
+    pub file: Option<String>,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl fmt::Display for AbortInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message.as_deref().unwrap_or("abort"))?;
+        if let Some(file) = &self.file {
+            write!(f, " at {file}:{}:{}", self.line, self.column)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RunOutcome {
+    /// JSON rendering of the return value, `None` for void functions.
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default)]
+    pub abort: Option<AbortInfo>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub stack: Option<String>,
+}
+
+impl RunOutcome {
+    pub fn is_success(&self) -> bool {
+        self.abort.is_none() && self.error.is_none()
+    }
+}
+
+/// Builds the project, then runs the result through the Node loader.
+pub fn build_and_run(
+    builder: &dyn WasmBuilder,
+    config: &BuildConfig,
+    options: &RunOptions,
+) -> PluginResult<RunOutcome> {
+    let result = builder.build(config)?;
+    run_build(&result, options, config.verbose)
+}
+
+pub fn run_build(
+    result: &BuildResult,
+    options: &RunOptions,
+    verbose: bool,
+) -> PluginResult<RunOutcome> {
+    let bindings = if options.ignore_bindings {
+        None
+    } else {
+        result
+            .js_path
+            .clone()
+            .or_else(|| find_esm_bindings(&result.wasm_path))
+    };
+    run_wasm(&result.wasm_path, bindings.as_deref(), options, verbose)
+}
+
+/// Runs a module, optionally through asc's generated ESM bindings.
+///
+/// Output from the module (`trace`, WASI stdout) is streamed as it happens.
+pub fn run_wasm(
+    wasm_path: &Path,
+    bindings_path: Option<&Path>,
+    options: &RunOptions,
+    verbose: bool,
+) -> PluginResult<RunOutcome> {
+    let node = options.node.as_deref().unwrap_or("node");
+    if !CommandExecutor::is_tool_installed(node) {
+        return Err(PluginError::CompileToolNotFound {
+            tool: node.to_string(),
+        });
+    }
+
+    let summary = inspect::inspect_file(wasm_path)?;
+    let export_name = options.export.clone().unwrap_or_default();
+    let param_types = export_param_types(&summary, &export_name);
+
+    let mut args: Vec<OsString> = vec![
+        "--input-type=module".into(),
+        "-e".into(),
+        NODE_LOADER.into(),
+        "--".into(),
+        absolute(wasm_path).into(),
+        bindings_path.map(absolute).unwrap_or_default().into(),
+        export_name.into(),
+        serde_json::to_string(&param_types)
+            .unwrap_or_else(|_| "[]".to_string())
+            .into(),
+    ];
+    args.extend(options.args.iter().map(OsString::from));
+
+    if verbose {
+        eprintln!("Executing: {node} <loader> {}", wasm_path.display());
+    }
+
+    let mut child = Command::new(node)
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(PluginError::Io)?;
+
+    let mut outcome = None;
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines() {
+            let line = line.map_err(PluginError::Io)?;
+            match line.strip_prefix(RESULT_MARKER) {
+                Some(json) => outcome = serde_json::from_str::<RunOutcome>(json).ok(),
+                None => println!("{line}"),
+            }
+        }
+    }
+
+    let status = child.wait().map_err(PluginError::Io)?;
+    match outcome {
+        Some(outcome) => Ok(outcome),
+        None => Err(PluginError::RuntimeFailed {
+            reason: format!("Node loader exited with {status} without reporting a result"),
+        }),
+    }
+}
+
+/// asc's `--bindings esm` writes `<name>.js` next to `<name>.wasm`.
+pub fn find_esm_bindings(wasm_path: impl AsRef<Path>) -> Option<PathBuf> {
+    let candidate = wasm_path.as_ref().with_extension("js");
+    let content = std::fs::read_to_string(&candidate).ok()?;
+    (content.contains("export") && content.contains("instantiate")).then_some(candidate)
+}
+
+fn export_param_types(summary: &inspect::ModuleSummary, export: &str) -> Vec<String> {
+    let name = if export.is_empty() { "_start" } else { export };
+    summary
+        .export(name)
+        .and_then(|e| e.signature.as_deref())
+        .and_then(|sig| sig.strip_prefix('('))
+        .and_then(|sig| sig.split(')').next())
+        .map(|params| {
+            params
+                .split(", ")
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
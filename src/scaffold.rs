@@ -0,0 +1,295 @@
+use crate::{PathResolver, PluginError, PluginResult};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const ASSEMBLYSCRIPT_VERSION: &str = "^0.27.0";
+pub const AS_PECT_VERSION: &str = "^8.1.0";
+pub const WASI_SHIM_VERSION: &str = "^0.1.0";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Template {
+    /// Exports functions for use from JS through asc's ESM bindings.
+    Library,
+    /// A command-line program run under WASI.
+    Wasi,
+    /// A library plus an `index.html` that loads it in the browser.
+    Web,
+}
+
+#[derive(Debug, Clone)]
+pub struct InitOptions {
+    pub template: Template,
+    /// Package name; defaults to the project directory name.
+    pub name: Option<String>,
+    /// Overwrite files that already exist.
+    pub force: bool,
+}
+
+impl Default for InitOptions {
+    fn default() -> Self {
+        Self {
+            template: Template::Library,
+            name: None,
+            force: false,
+        }
+    }
+}
+
+/// Writes a new AssemblyScript project into `project_path` and returns the
+/// files it created.
+///
+/// Nothing is written if any of the template's files already exist, unless
+/// `force` is set.
+pub fn init_project(
+    project_path: impl AsRef<Path>,
+    options: &InitOptions,
+) -> PluginResult<Vec<PathBuf>> {
+    let project_path = project_path.as_ref();
+    let name = match &options.name {
+        Some(name) => name.clone(),
+        None => default_name(project_path),
+    };
+    let files = template_files(options.template, &name);
+
+    if !options.force {
+        let existing: Vec<&str> = files
+            .iter()
+            .map(|(path, _)| *path)
+            .filter(|path| project_path.join(path).exists())
+            .collect();
+        if !existing.is_empty() {
+            return Err(PluginError::InvalidProjectStructure {
+                reason: format!(
+                    "Refusing to overwrite existing files: {} (use --force to replace them)",
+                    existing.join(", ")
+                ),
+            });
+        }
+    }
+
+    let mut created = Vec::new();
+    for (path, content) in files {
+        let target = project_path.join(path);
+        if let Some(parent) = target.parent() {
+            PathResolver::ensure_output_directory(parent)?;
+        }
+        fs::write(&target, content).map_err(PluginError::Io)?;
+        created.push(target);
+    }
+
+    Ok(created)
+}
+
+/// The files a template generates, as paths relative to the project root.
+pub fn template_files(template: Template, name: &str) -> Vec<(&'static str, String)> {
+    let mut files = vec![
+        ("package.json", package_json(template, name)),
+        ("asconfig.json", asconfig_json(template)),
+        ("tsconfig.json", TSCONFIG_JSON.to_string()),
+        (".gitignore", GITIGNORE.to_string()),
+        ("as-pect.config.js", ASPECT_CONFIG.to_string()),
+        ("assembly/__tests__/as-pect.d.ts", ASPECT_TYPES.to_string()),
+    ];
+
+    match template {
+        Template::Library | Template::Web => {
+            files.push(("assembly/index.ts", LIBRARY_INDEX.to_string()));
+            files.push(("assembly/__tests__/index.spec.ts", LIBRARY_SPEC.to_string()));
+        }
+        Template::Wasi => {
+            files.push(("assembly/index.ts", WASI_INDEX.to_string()));
+            files.push(("assembly/greet.ts", WASI_GREET.to_string()));
+            files.push(("assembly/__tests__/greet.spec.ts", WASI_SPEC.to_string()));
+        }
+    }
+
+    if template == Template::Web {
+        files.push(("index.html", web_index_html(name)));
+    }
+
+    files
+}
+
+fn default_name(project_path: &Path) -> String {
+    let path = fs::canonicalize(project_path).unwrap_or_else(|_| project_path.to_path_buf());
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let name: String = name
+        .to_ascii_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let name = name.trim_matches('-');
+
+    if name.is_empty() {
+        "assemblyscript-project".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+fn package_json(template: Template, name: &str) -> String {
+    let start = match template {
+        Template::Wasi => "\n    \"start\": \"wasmasc run\",",
+        Template::Web => "\n    \"start\": \"npx serve .\",",
+        Template::Library => "",
+    };
+    let exports = match template {
+        Template::Wasi => "",
+        Template::Library | Template::Web => {
+            "\n  \"exports\": {\n    \".\": {\n      \"import\": \"./build/release.js\",\n      \"types\": \"./build/release.d.ts\"\n    }\n  },"
+        }
+    };
+    let wasi_shim = match template {
+        Template::Wasi => format!("\n    \"@assemblyscript/wasi-shim\": \"{WASI_SHIM_VERSION}\","),
+        Template::Library | Template::Web => String::new(),
+    };
+    let name = serde_json::to_string(name).unwrap_or_default();
+
+    format!(
+        r#"{{
+  "name": {name},
+  "version": "0.1.0",
+  "private": true,
+  "type": "module",{exports}
+  "scripts": {{
+    "asbuild:debug": "asc assembly/index.ts --target debug",
+    "asbuild:release": "asc assembly/index.ts --target release",
+    "asbuild": "asc assembly/index.ts --target debug && asc assembly/index.ts --target release",
+    "build": "asc assembly/index.ts --target release",{start}
+    "test": "asp --verbose"
+  }},
+  "devDependencies": {{
+    "@as-pect/cli": "{AS_PECT_VERSION}",{wasi_shim}
+    "assemblyscript": "{ASSEMBLYSCRIPT_VERSION}"
+  }}
+}}
+"#
+    )
+}
+
+fn asconfig_json(template: Template) -> String {
+    let (extends, options) = match template {
+        Template::Wasi => (
+            "\n  \"extends\": \"./node_modules/@assemblyscript/wasi-shim/asconfig.json\",",
+            "{}",
+        ),
+        Template::Library | Template::Web => ("", "{\n    \"bindings\": \"esm\"\n  }"),
+    };
+
+    format!(
+        r#"{{{extends}
+  "targets": {{
+    "debug": {{
+      "outFile": "build/debug.wasm",
+      "textFile": "build/debug.wat",
+      "sourceMap": true,
+      "debug": true
+    }},
+    "release": {{
+      "outFile": "build/release.wasm",
+      "textFile": "build/release.wat",
+      "sourceMap": true,
+      "optimizeLevel": 3,
+      "shrinkLevel": 0,
+      "converge": false,
+      "noAssert": false
+    }}
+  }},
+  "options": {options}
+}}
+"#
+    )
+}
+
+fn web_index_html(name: &str) -> String {
+    let title = name
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    WEB_INDEX_HTML.replace("{name}", &title)
+}
+
+const TSCONFIG_JSON: &str = r#"{
+  "extends": "assemblyscript/std/assembly.json",
+  "include": ["./assembly/**/*.ts"]
+}
+"#;
+
+const GITIGNORE: &str = "node_modules/\nbuild/\ndist/\n.wasmasc/\n";
+
+const ASPECT_CONFIG: &str = r#"export default {
+  include: ["assembly/__tests__/**/*.spec.ts"],
+  add: ["assembly/__tests__/**/*.include.ts"],
+  flags: {},
+  disclude: [/node_modules/],
+  imports(memory, createImports, instantiateSync, binary) {
+    let instance;
+    const imports = createImports({});
+    instance = instantiateSync(binary, imports);
+    return instance;
+  },
+  outputBinary: false,
+};
+"#;
+
+const ASPECT_TYPES: &str = "/// <reference types=\"@as-pect/assembly/types/as-pect\" />\n";
+
+const LIBRARY_INDEX: &str = r#"export function add(a: i32, b: i32): i32 {
+  return a + b;
+}
+"#;
+
+const LIBRARY_SPEC: &str = r#"import { add } from "../index";
+
+describe("add", () => {
+  it("adds two numbers", () => {
+    expect(add(1, 2)).toBe(3);
+  });
+});
+"#;
+
+const WASI_INDEX: &str = r#"import { greet } from "./greet";
+
+console.log(greet("WASI"));
+"#;
+
+const WASI_GREET: &str = r#"export function greet(name: string): string {
+  return "Hello, " + name + "!";
+}
+"#;
+
+const WASI_SPEC: &str = r#"import { greet } from "../greet";
+
+describe("greet", () => {
+  it("greets by name", () => {
+    expect(greet("WASI")).toBe("Hello, WASI!");
+  });
+});
+"#;
+
+const WEB_INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>{name}</title>
+</head>
+<body>
+  <p>1 + 2 = <output id="result">…</output></p>
+  <script type="module">
+    import { add } from "./build/release.js";
+    document.getElementById("result").textContent = add(1, 2);
+  </script>
+</body>
+</html>
+"#;
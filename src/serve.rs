@@ -0,0 +1,374 @@
+use crate::{output, BuildConfig, PluginResult, WasmBuilder};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Server-sent events stream pages subscribe to for reloads.
+pub const EVENTS_PATH: &str = "/__wasmasc/events";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Injected into HTML pages while watching.
+const RELOAD_SCRIPT: &str = r#"<script>new EventSource("/__wasmasc/events").addEventListener("reload", () => location.reload());</script>"#;
+
+/// Directories never watched for changes.
+const IGNORED_DIRS: &[&str] = &["node_modules", ".git", ".wasmasc"];
+
+#[derive(Debug, Clone)]
+pub struct ServeOptions {
+    pub host: String,
+    pub port: u16,
+    /// Rebuild when project files change and reload open pages.
+    pub watch: bool,
+}
+
+impl Default for ServeOptions {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            watch: true,
+        }
+    }
+}
+
+type Clients = Arc<Mutex<Vec<TcpStream>>>;
+
+/// Builds the project, then serves the output directory until the process
+/// is stopped. Every response carries COOP/COEP headers so shared-memory
+/// modules can use `SharedArrayBuffer`.
+///
+/// While watching, the project is polled for changes and rebuilt, and pages
+/// are told to reload over [`EVENTS_PATH`] after each successful rebuild. A
+/// failed rebuild is reported and the last good build keeps being served.
+pub fn serve(
+    builder: &dyn WasmBuilder,
+    config: &BuildConfig,
+    options: &ServeOptions,
+) -> PluginResult<()> {
+    builder.build(config)?;
+
+    let listener = TcpListener::bind((options.host.as_str(), options.port))?;
+    println!(
+        "🌐 Serving {} at http://{}",
+        config.output_dir.display(),
+        listener.local_addr()?
+    );
+
+    let clients: Clients = Arc::default();
+    let root = config.output_dir.clone();
+    let watch = options.watch;
+    let accept_clients = Arc::clone(&clients);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (root, clients) = (root.clone(), Arc::clone(&accept_clients));
+            thread::spawn(move || {
+                let _ = handle(stream, &root, watch, &clients);
+            });
+        }
+    });
+
+    if !watch {
+        loop {
+            thread::park();
+        }
+    }
+
+    println!("👀 Watching {} for changes", config.project_path.display());
+    let output_dir = std::path::absolute(&config.output_dir)?;
+    let mut known = snapshot(&config.project_path, &output_dir);
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        if snapshot(&config.project_path, &output_dir) == known {
+            continue;
+        }
+        // Let editors finish writing before building.
+        thread::sleep(POLL_INTERVAL);
+
+        println!("🔄 Change detected, rebuilding...");
+        match builder.build(config) {
+            Ok(_) => {
+                let reloaded = broadcast_reload(&clients);
+                println!("✅ Rebuilt; reloading {reloaded} page(s)");
+            }
+            Err(e) => {
+                eprintln!("❌ Rebuild failed [{}]: {e}", e.code());
+                if let Some(hint) = e.hint() {
+                    eprintln!("   → {hint}");
+                }
+            }
+        }
+        // Taken after the build, so files the build script writes into the
+        // project don't trigger another one.
+        known = snapshot(&config.project_path, &output_dir);
+    }
+}
+
+/// Modification time and size of every file in the project, outside the
+/// output directory and `IGNORED_DIRS`.
+fn snapshot(project_path: &Path, output_dir: &Path) -> BTreeMap<PathBuf, (SystemTime, u64)> {
+    let mut files = BTreeMap::new();
+    let mut dirs = vec![project_path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                let ignored = IGNORED_DIRS.iter().any(|d| entry.file_name() == *d)
+                    || std::path::absolute(&path).is_ok_and(|p| p == output_dir);
+                if !ignored {
+                    dirs.push(path);
+                }
+            } else if let Ok(modified) = metadata.modified() {
+                files.insert(path, (modified, metadata.len()));
+            }
+        }
+    }
+    files
+}
+
+/// Sends a reload event to every connected page and returns how many got it.
+fn broadcast_reload(clients: &Clients) -> usize {
+    let mut clients = clients.lock().unwrap_or_else(|e| e.into_inner());
+    clients.retain_mut(|stream| {
+        stream
+            .write_all(b"event: reload\ndata: {}\n\n")
+            .and_then(|_| stream.flush())
+            .is_ok()
+    });
+    clients.len()
+}
+
+fn handle(stream: TcpStream, root: &Path, watch: bool, clients: &Clients) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or("/"));
+    let path = target.split(['?', '#']).next().unwrap_or("/");
+    let mut stream = stream;
+
+    let head = method == "HEAD";
+    if method != "GET" && !head {
+        return respond(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"",
+            head,
+        );
+    }
+    if path == EVENTS_PATH && watch {
+        stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+              Cache-Control: no-cache\r\nConnection: keep-alive\r\n\r\nretry: 1000\n\n",
+        )?;
+        stream.flush()?;
+        clients
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(stream);
+        return Ok(());
+    }
+
+    let Some(mut file) = resolve(root, path) else {
+        return respond(
+            &mut stream,
+            "404 Not Found",
+            "text/plain",
+            b"Not found",
+            head,
+        );
+    };
+    if file.is_dir() {
+        file = file.join("index.html");
+    }
+    let Ok(mut body) = fs::read(&file) else {
+        return respond(
+            &mut stream,
+            "404 Not Found",
+            "text/plain",
+            b"Not found",
+            head,
+        );
+    };
+
+    let content_type = content_type(&file);
+    if watch && content_type.starts_with("text/html") {
+        body = inject_reload(&body);
+    }
+    respond(&mut stream, "200 OK", content_type, &body, head)
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+    head: bool,
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Cache-Control: no-cache\r\nCross-Origin-Opener-Policy: same-origin\r\n\
+         Cross-Origin-Embedder-Policy: require-corp\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    if !head {
+        stream.write_all(body)?;
+    }
+    stream.flush()
+}
+
+/// Maps a URL path into `root`, refusing anything that would leave it and
+/// wasmasc's own files in the output directory.
+fn resolve(root: &Path, url_path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode(url_path)?;
+    let mut path = root.to_path_buf();
+    for component in Path::new(decoded.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(part) => {
+                let part_name = part.to_string_lossy();
+                let hidden = part_name == output::LOCK_FILE
+                    || part_name.starts_with(output::STAGING_PREFIX)
+                    || part_name.ends_with(".partial");
+                if hidden {
+                    return None;
+                }
+                path.push(part);
+            }
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(path)
+}
+
+fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match name.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("wasm") => "application/wasm",
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("js" | "mjs") => "text/javascript; charset=utf-8",
+        Some("json" | "map") => "application/json",
+        Some("css") => "text/css; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("ico") => "image/x-icon",
+        Some("txt" | "ts" | "wat") => "text/plain; charset=utf-8",
+        Some("gz") => "application/gzip",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Adds [`RELOAD_SCRIPT`] before `</body>`, or at the end without one.
+fn inject_reload(html: &[u8]) -> Vec<u8> {
+    let text = String::from_utf8_lossy(html);
+    match text.rfind("</body>") {
+        Some(at) => format!("{}{RELOAD_SCRIPT}\n{}", &text[..at], &text[at..]).into_bytes(),
+        None => format!("{text}{RELOAD_SCRIPT}\n").into_bytes(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{inject_reload, percent_decode, resolve, RELOAD_SCRIPT};
+    use crate::output::{LOCK_FILE, STAGING_PREFIX};
+    use std::path::Path;
+
+    #[test]
+    fn resolves_paths_under_the_root() {
+        let root = Path::new("/srv/dist");
+        assert_eq!(resolve(root, "/"), Some(root.to_path_buf()));
+        assert_eq!(resolve(root, "/index.html"), Some(root.join("index.html")));
+        assert_eq!(resolve(root, "/./a/b.wasm"), Some(root.join("a/b.wasm")));
+        assert_eq!(resolve(root, "/my%20app.js"), Some(root.join("my app.js")));
+    }
+
+    #[test]
+    fn rejects_traversal_plain_or_encoded() {
+        let root = Path::new("/srv/dist");
+        for path in [
+            "/../secret",
+            "/a/../../secret",
+            "/%2e%2e/secret",
+            "/%2E%2E%2fsecret",
+            "/a/%2e%2e/%2e%2e/secret",
+        ] {
+            assert_eq!(resolve(root, path), None, "{path}");
+        }
+        assert_eq!(resolve(root, "//etc/passwd"), Some(root.join("etc/passwd")));
+    }
+
+    #[test]
+    fn hides_lock_staging_and_partial_files() {
+        let root = Path::new("/srv/dist");
+        for path in [
+            format!("/{LOCK_FILE}"),
+            format!("/{STAGING_PREFIX}123/index.wasm"),
+            format!("/{STAGING_PREFIX}123"),
+            "/sub/.wasmasc.lock".to_string(),
+            "/index.wasm.4242.partial".to_string(),
+            "/%2ewasmasc%2elock".to_string(),
+        ] {
+            assert_eq!(resolve(root, &path), None, "{path}");
+        }
+    }
+
+    #[test]
+    fn percent_decoding_rejects_malformed_escapes() {
+        assert_eq!(percent_decode("a%2Fb").as_deref(), Some("a/b"));
+        assert_eq!(percent_decode("%e6%97%a5").as_deref(), Some("日"));
+        assert_eq!(percent_decode("%"), None);
+        assert_eq!(percent_decode("%zz"), None);
+        assert_eq!(percent_decode("%ff"), None);
+    }
+
+    #[test]
+    fn reload_script_goes_before_the_last_body_close() {
+        let html = inject_reload(b"<body><p>x</p></body></html>");
+        assert_eq!(
+            String::from_utf8(html).unwrap(),
+            format!("<body><p>x</p>{RELOAD_SCRIPT}\n</body></html>")
+        );
+        let html = inject_reload(b"<p>no body</p>");
+        assert!(String::from_utf8(html)
+            .unwrap()
+            .ends_with(&format!("{RELOAD_SCRIPT}\n")));
+    }
+}
@@ -0,0 +1,494 @@
+use crate::inspect::{parse_error, ModuleNames};
+use crate::sourcemap::SourceMap;
+use crate::{PathResolver, PluginError, PluginResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use wasmparser::{Parser, Payload, TypeRef};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SectionSize {
+    pub name: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FunctionSize {
+    pub index: u32,
+    pub name: Option<String>,
+    pub origin: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DataSegmentSize {
+    pub index: u32,
+    pub name: Option<String>,
+    pub origin: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OriginSize {
+    pub origin: String,
+    pub user_code: bool,
+    pub functions: usize,
+    pub code_size: u64,
+    pub data_size: u64,
+}
+
+impl OriginSize {
+    pub fn total(&self) -> u64 {
+        self.code_size + self.data_size
+    }
+}
+
+/// Where the bytes of a module go, by section, function and source origin.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SizeReport {
+    pub total_size: u64,
+    pub sections: Vec<SectionSize>,
+    pub functions: Vec<FunctionSize>,
+    pub data_segments: Vec<DataSegmentSize>,
+    pub origins: Vec<OriginSize>,
+    /// False when the module has no name section, in which case origins fall
+    /// back to the source map or are reported as unknown.
+    pub has_names: bool,
+}
+
+impl SizeReport {
+    pub fn from_file(wasm_path: impl AsRef<Path>) -> PluginResult<Self> {
+        let wasm = fs::read(&wasm_path).map_err(PluginError::Io)?;
+        let map_path = PathResolver::append_suffix(wasm_path, ".map");
+        let source_map = if map_path.exists() {
+            SourceMap::from_file(&map_path).ok()
+        } else {
+            None
+        };
+        Self::analyze(&wasm, source_map.as_ref())
+    }
+
+    pub fn analyze(wasm: &[u8], source_map: Option<&SourceMap>) -> PluginResult<Self> {
+        let mut sections = Vec::new();
+        let mut bodies = Vec::new();
+        let mut segments = Vec::new();
+        let mut names = ModuleNames::default();
+        let mut imported_functions = 0u32;
+
+        for payload in Parser::new(0).parse_all(wasm) {
+            let payload = payload.map_err(parse_error)?;
+
+            if let Some((id, range)) = payload.as_section() {
+                let name = match &payload {
+                    Payload::CustomSection(reader) => format!("custom:{}", reader.name()),
+                    _ => section_name(id).to_string(),
+                };
+                sections.push(SectionSize {
+                    name,
+                    size: range.end - range.start,
+                });
+            }
+
+            match payload {
+                Payload::ImportSection(reader) => {
+                    for import in reader.into_imports() {
+                        if matches!(
+                            import.map_err(parse_error)?.ty,
+                            TypeRef::Func(_) | TypeRef::FuncExact(_)
+                        ) {
+                            imported_functions += 1;
+                        }
+                    }
+                }
+                Payload::CodeSectionEntry(body) => bodies.push(body.range()),
+                Payload::DataSection(reader) => {
+                    for data in reader {
+                        let data = data.map_err(parse_error)?;
+                        segments.push(data.data.len() as u64);
+                    }
+                }
+                Payload::CustomSection(reader) => names.read(&reader),
+                _ => {}
+            }
+        }
+
+        let has_names = !names.functions.is_empty();
+
+        let mut functions: Vec<FunctionSize> = bodies
+            .iter()
+            .enumerate()
+            .map(|(i, range)| {
+                let index = imported_functions + i as u32;
+                let name = names.functions.get(&index).cloned();
+                let origin = match &name {
+                    Some(name) => origin_of(name),
+                    None => source_map
+                        .and_then(|map| map.lookup(range.start))
+                        .map(|loc| origin_of_source(&loc.source))
+                        .unwrap_or_else(|| UNKNOWN_ORIGIN.to_string()),
+                };
+                FunctionSize {
+                    index,
+                    name,
+                    origin,
+                    size: range.end - range.start,
+                }
+            })
+            .collect();
+        functions.sort_by(|a, b| b.size.cmp(&a.size).then(a.index.cmp(&b.index)));
+
+        let data_segments: Vec<DataSegmentSize> = segments
+            .into_iter()
+            .enumerate()
+            .map(|(i, size)| {
+                let name = names.data.get(&(i as u32)).cloned();
+                let origin = name
+                    .as_deref()
+                    .map(origin_of)
+                    .unwrap_or_else(|| DATA_ORIGIN.to_string());
+                DataSegmentSize {
+                    index: i as u32,
+                    name,
+                    origin,
+                    size,
+                }
+            })
+            .collect();
+
+        let mut origins: BTreeMap<String, OriginSize> = BTreeMap::new();
+        for function in &functions {
+            let entry = origins
+                .entry(function.origin.clone())
+                .or_insert_with(|| new_origin(&function.origin));
+            entry.functions += 1;
+            entry.code_size += function.size;
+        }
+        for segment in &data_segments {
+            origins
+                .entry(segment.origin.clone())
+                .or_insert_with(|| new_origin(&segment.origin))
+                .data_size += segment.size;
+        }
+
+        let mut origins: Vec<OriginSize> = origins.into_values().collect();
+        origins.sort_by(|a, b| b.total().cmp(&a.total()).then(a.origin.cmp(&b.origin)));
+
+        Ok(Self {
+            total_size: wasm.len() as u64,
+            sections,
+            functions,
+            data_segments,
+            origins,
+            has_names,
+        })
+    }
+
+    pub fn user_code_size(&self) -> u64 {
+        self.origins
+            .iter()
+            .filter(|o| o.user_code)
+            .map(|o| o.total())
+            .sum()
+    }
+
+    pub fn section_size(&self, name: &str) -> Option<u64> {
+        let sizes: Vec<u64> = self
+            .sections
+            .iter()
+            .filter(|s| s.name == name)
+            .map(|s| s.size)
+            .collect();
+        (!sizes.is_empty()).then(|| sizes.iter().sum())
+    }
+
+    /// Formats the report with at most `top` functions listed.
+    pub fn display(&self, top: usize) -> SizeReportDisplay<'_> {
+        SizeReportDisplay { report: self, top }
+    }
+}
+
+const UNKNOWN_ORIGIN: &str = "<unknown>";
+const DATA_ORIGIN: &str = "<data>";
+
+fn new_origin(origin: &str) -> OriginSize {
+    OriginSize {
+        origin: origin.to_string(),
+        user_code: is_user_origin(origin),
+        functions: 0,
+        code_size: 0,
+        data_size: 0,
+    }
+}
+
+fn is_user_origin(origin: &str) -> bool {
+    !origin.starts_with("~lib/") && !origin.starts_with('<')
+}
+
+/// Derives a source origin from an AssemblyScript internal name such as
+/// `~lib/rt/itcms/__new` or `assembly/index/Foo#bar`.
+///
+/// Standard library names are grouped by their top-level module (`~lib/rt`),
+/// user names by the file that defined them (`assembly/index`).
+pub fn origin_of(name: &str) -> String {
+    if let Some(module) = name.strip_prefix("start:") {
+        return origin_of_source(module);
+    }
+
+    // Generic arguments and member names may contain slashes of their own.
+    let path_end = name.find(['<', '#']).unwrap_or(name.len());
+    let path = &name[..path_end];
+
+    if let Some(lib) = path.strip_prefix("~lib/") {
+        return match lib.split('/').next() {
+            Some(module) if !module.is_empty() && lib.contains('/') => format!("~lib/{module}"),
+            _ => "~lib".to_string(),
+        };
+    }
+
+    match path.rfind('/') {
+        Some(pos) => path[..pos].to_string(),
+        None => "<generated>".to_string(),
+    }
+}
+
+fn origin_of_source(source: &str) -> String {
+    let path = source.trim_end_matches(".ts");
+    if let Some(lib) = path.strip_prefix("~lib/") {
+        return match lib.split('/').next() {
+            Some(module) if lib.contains('/') => format!("~lib/{module}"),
+            _ => format!("~lib/{lib}"),
+        };
+    }
+    path.to_string()
+}
+
+fn section_name(id: u8) -> &'static str {
+    match id {
+        1 => "type",
+        2 => "import",
+        3 => "function",
+        4 => "table",
+        5 => "memory",
+        6 => "global",
+        7 => "export",
+        8 => "start",
+        9 => "element",
+        10 => "code",
+        11 => "data",
+        12 => "datacount",
+        13 => "tag",
+        _ => "unknown",
+    }
+}
+
+pub struct SizeReportDisplay<'a> {
+    report: &'a SizeReport,
+    top: usize,
+}
+
+impl fmt::Display for SizeReportDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let report = self.report;
+        writeln!(f, "Total: {} bytes", report.total_size)?;
+        if !report.has_names {
+            writeln!(
+                f,
+                "⚠️  No name section; build with --debug to attribute functions by name"
+            )?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "{:<20} {:>10} {:>7}", "SECTION", "BYTES", "%")?;
+        for section in &report.sections {
+            writeln!(
+                f,
+                "{:<20} {:>10} {:>6.1}%",
+                section.name,
+                section.size,
+                percent(section.size, report.total_size)
+            )?;
+        }
+        writeln!(f)?;
+
+        writeln!(
+            f,
+            "{:<28} {:>6} {:>10} {:>10} {:>7}",
+            "ORIGIN", "FUNCS", "CODE", "DATA", "%"
+        )?;
+        for origin in &report.origins {
+            writeln!(
+                f,
+                "{:<28} {:>6} {:>10} {:>10} {:>6.1}%",
+                origin.origin,
+                origin.functions,
+                origin.code_size,
+                origin.data_size,
+                percent(origin.total(), report.total_size)
+            )?;
+        }
+        writeln!(
+            f,
+            "User code: {} bytes ({:.1}%)",
+            report.user_code_size(),
+            percent(report.user_code_size(), report.total_size)
+        )?;
+
+        if self.top > 0 && !report.functions.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "{:>10}  FUNCTION", "BYTES")?;
+            for function in report.functions.iter().take(self.top) {
+                let name = function
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("func[{}]", function.index));
+                writeln!(f, "{:>10}  {name}", function.size)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SizeDelta {
+    pub key: String,
+    pub before: u64,
+    pub after: u64,
+}
+
+impl SizeDelta {
+    pub fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+/// Differences between two size reports, largest changes first.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SizeDiff {
+    pub total: SizeDelta,
+    pub sections: Vec<SizeDelta>,
+    pub origins: Vec<SizeDelta>,
+    pub functions: Vec<SizeDelta>,
+}
+
+impl SizeDiff {
+    pub fn between(before: &SizeReport, after: &SizeReport) -> Self {
+        let sections = diff_by(
+            before.sections.iter().map(|s| (s.name.clone(), s.size)),
+            after.sections.iter().map(|s| (s.name.clone(), s.size)),
+        );
+        let origins = diff_by(
+            before.origins.iter().map(|o| (o.origin.clone(), o.total())),
+            after.origins.iter().map(|o| (o.origin.clone(), o.total())),
+        );
+        // Function indices shift between builds, so only named functions can
+        // be matched up.
+        let functions = diff_by(
+            before
+                .functions
+                .iter()
+                .filter_map(|f| Some((f.name.clone()?, f.size))),
+            after
+                .functions
+                .iter()
+                .filter_map(|f| Some((f.name.clone()?, f.size))),
+        );
+
+        Self {
+            total: SizeDelta {
+                key: "total".to_string(),
+                before: before.total_size,
+                after: after.total_size,
+            },
+            sections,
+            origins,
+            functions,
+        }
+    }
+
+    pub fn display(&self, top: usize) -> SizeDiffDisplay<'_> {
+        SizeDiffDisplay { diff: self, top }
+    }
+}
+
+fn diff_by(
+    before: impl Iterator<Item = (String, u64)>,
+    after: impl Iterator<Item = (String, u64)>,
+) -> Vec<SizeDelta> {
+    let mut entries: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    for (key, size) in before {
+        entries.entry(key).or_default().0 += size;
+    }
+    for (key, size) in after {
+        entries.entry(key).or_default().1 += size;
+    }
+
+    let mut deltas: Vec<SizeDelta> = entries
+        .into_iter()
+        .filter(|(_, (before, after))| before != after)
+        .map(|(key, (before, after))| SizeDelta { key, before, after })
+        .collect();
+    deltas.sort_by(|a, b| {
+        b.delta()
+            .abs()
+            .cmp(&a.delta().abs())
+            .then(a.key.cmp(&b.key))
+    });
+    deltas
+}
+
+pub struct SizeDiffDisplay<'a> {
+    diff: &'a SizeDiff,
+    top: usize,
+}
+
+impl fmt::Display for SizeDiffDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = &self.diff.total;
+        writeln!(
+            f,
+            "Total: {} -> {} bytes ({:+})",
+            total.before,
+            total.after,
+            total.delta()
+        )?;
+
+        for (title, deltas) in [
+            ("SECTION", &self.diff.sections),
+            ("ORIGIN", &self.diff.origins),
+            ("FUNCTION", &self.diff.functions),
+        ] {
+            if deltas.is_empty() {
+                continue;
+            }
+            writeln!(f)?;
+            writeln!(
+                f,
+                "{:>10} {:>10} {:>9}  {title}",
+                "BEFORE", "AFTER", "DELTA"
+            )?;
+            for delta in deltas.iter().take(self.top.max(1)) {
+                writeln!(
+                    f,
+                    "{:>10} {:>10} {:>+9}  {}",
+                    delta.before,
+                    delta.after,
+                    delta.delta(),
+                    delta.key
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
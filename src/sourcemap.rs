@@ -0,0 +1,236 @@
+use crate::{PluginError, PluginResult};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSourceMap {
+    version: u32,
+    #[serde(default)]
+    source_root: Option<String>,
+    sources: Vec<Option<String>>,
+    #[serde(default)]
+    names: Vec<String>,
+    mappings: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceLocation {
+    pub source: String,
+    pub line: u32,
+    pub column: u32,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Mapping {
+    generated_column: u64,
+    source: Option<u32>,
+    line: u32,
+    column: u32,
+    name: Option<u32>,
+}
+
+/// A decoded source map for a wasm module.
+///
+/// AssemblyScript emits the whole binary as a single generated "line", so
+/// generated columns are byte offsets into the module.
+#[derive(Debug, Clone)]
+pub struct SourceMap {
+    sources: Vec<String>,
+    names: Vec<String>,
+    mappings: Vec<Mapping>,
+}
+
+impl SourceMap {
+    pub fn from_file(path: impl AsRef<Path>) -> PluginResult<Self> {
+        let content = fs::read_to_string(path).map_err(PluginError::Io)?;
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> PluginResult<Self> {
+        let raw: RawSourceMap =
+            serde_json::from_str(content).map_err(|e| PluginError::InvalidSourceMap {
+                reason: e.to_string(),
+            })?;
+
+        if raw.version != 3 {
+            return Err(PluginError::InvalidSourceMap {
+                reason: format!("unsupported source map version {}", raw.version),
+            });
+        }
+
+        let root = raw.source_root.unwrap_or_default();
+        let sources = raw
+            .sources
+            .into_iter()
+            .map(|s| {
+                let s = s.unwrap_or_default();
+                if root.is_empty() {
+                    s
+                } else {
+                    Path::new(&root).join(s).to_string_lossy().to_string()
+                }
+            })
+            .collect();
+
+        let mut mappings = decode_mappings(&raw.mappings)?;
+        mappings.sort_by_key(|m| m.generated_column);
+
+        Ok(Self {
+            sources,
+            names: raw.names,
+            mappings,
+        })
+    }
+
+    pub fn sources(&self) -> &[String] {
+        &self.sources
+    }
+
+    /// Finds the original location for a module byte offset, using the closest
+    /// preceding mapping.
+    pub fn lookup(&self, offset: u64) -> Option<SourceLocation> {
+        let idx = self
+            .mappings
+            .partition_point(|m| m.generated_column <= offset);
+        let mapping = self.mappings[..idx].last()?;
+
+        Some(SourceLocation {
+            source: self.sources.get(mapping.source? as usize)?.clone(),
+            line: mapping.line + 1,
+            column: mapping.column + 1,
+            name: mapping
+                .name
+                .and_then(|n| self.names.get(n as usize))
+                .cloned(),
+        })
+    }
+}
+
+fn decode_mappings(mappings: &str) -> PluginResult<Vec<Mapping>> {
+    let mut result = Vec::new();
+    let mut source: i64 = 0;
+    let mut line: i64 = 0;
+    let mut column: i64 = 0;
+    let mut name: i64 = 0;
+
+    for (line_idx, generated_line) in mappings.split(';').enumerate() {
+        let mut generated_column: i64 = 0;
+
+        for segment in generated_line.split(',').filter(|s| !s.is_empty()) {
+            let fields = decode_vlq_segment(segment)?;
+            generated_column += fields[0];
+
+            let mut mapping = Mapping {
+                generated_column: generated_column.max(0) as u64,
+                source: None,
+                line: 0,
+                column: 0,
+                name: None,
+            };
+
+            if fields.len() >= 4 {
+                source += fields[1];
+                line += fields[2];
+                column += fields[3];
+                mapping.source = Some(source.max(0) as u32);
+                mapping.line = line.max(0) as u32;
+                mapping.column = column.max(0) as u32;
+            }
+
+            if fields.len() >= 5 {
+                name += fields[4];
+                mapping.name = Some(name.max(0) as u32);
+            }
+
+            // Only the first generated line carries module byte offsets; later
+            // lines still have to be decoded because field deltas carry over.
+            if line_idx == 0 {
+                result.push(mapping);
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+fn decode_vlq_segment(segment: &str) -> PluginResult<Vec<i64>> {
+    let mut values = Vec::new();
+    let mut value: i64 = 0;
+    let mut shift = 0;
+
+    for c in segment.bytes() {
+        let digit = base64_value(c).ok_or_else(|| PluginError::InvalidSourceMap {
+            reason: format!("invalid base64 character '{}' in mappings", c as char),
+        })?;
+
+        value += ((digit & 0b11111) as i64) << shift;
+        if digit & 0b100000 != 0 {
+            shift += 5;
+            if shift > 60 {
+                return Err(PluginError::InvalidSourceMap {
+                    reason: "VLQ value overflow in mappings".to_string(),
+                });
+            }
+        } else {
+            let negative = value & 1 == 1;
+            value >>= 1;
+            values.push(if negative { -value } else { value });
+            value = 0;
+            shift = 0;
+        }
+    }
+
+    if shift != 0 || values.is_empty() {
+        return Err(PluginError::InvalidSourceMap {
+            reason: format!("truncated mapping segment '{segment}'"),
+        });
+    }
+
+    Ok(values)
+}
+
+fn base64_value(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_mappings, decode_vlq_segment};
+
+    #[test]
+    fn decodes_single_and_multi_digit_values() {
+        assert_eq!(decode_vlq_segment("AAAA").unwrap(), [0, 0, 0, 0]);
+        assert_eq!(decode_vlq_segment("CADF").unwrap(), [1, 0, -1, -2]);
+        assert_eq!(decode_vlq_segment("gB").unwrap(), [16]);
+        assert_eq!(decode_vlq_segment("hB").unwrap(), [-16]);
+        assert_eq!(decode_vlq_segment("2HwK").unwrap(), [123, 168]);
+    }
+
+    #[test]
+    fn rejects_invalid_and_truncated_segments() {
+        assert!(decode_vlq_segment("A!").is_err());
+        assert!(decode_vlq_segment("g").is_err());
+        assert!(decode_vlq_segment("").is_err());
+        assert!(decode_vlq_segment("gggggggggggggA").is_err());
+    }
+
+    #[test]
+    fn mapping_deltas_accumulate_across_lines() {
+        let mappings = decode_mappings("AAAA,CACC;AAAA").unwrap();
+        assert_eq!(mappings.len(), 2);
+        assert_eq!(mappings[1].generated_column, 1);
+        assert_eq!(mappings[1].source, Some(0));
+        assert_eq!((mappings[1].line, mappings[1].column), (1, 1));
+        assert_eq!(mappings[1].name, None);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::inspect::{parse_error, ModuleNames};
+use crate::sourcemap::{SourceLocation, SourceMap};
+use crate::{PathResolver, PluginError, PluginResult};
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use wasmparser::{Parser, Payload, TypeRef};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameOffset {
+    /// Byte offset from the start of the module, as printed by V8 and wasmtime.
+    Module(u64),
+    /// Byte offset from the start of the function body, as in `func[3]+0x1c`.
+    Function(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RawFrame {
+    pub func_index: Option<u32>,
+    pub offset: FrameOffset,
+}
+
+#[derive(Debug, Clone)]
+pub struct SymbolicatedFrame {
+    pub func_index: Option<u32>,
+    pub function_name: Option<String>,
+    pub module_offset: u64,
+    pub location: Option<SourceLocation>,
+}
+
+impl fmt::Display for SymbolicatedFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match (&self.function_name, self.func_index) {
+            (Some(name), _) => name.clone(),
+            (None, Some(idx)) => format!("wasm-function[{idx}]"),
+            (None, None) => "<unknown>".to_string(),
+        };
+
+        match &self.location {
+            Some(loc) => write!(f, "at {name} ({}:{}:{})", loc.source, loc.line, loc.column),
+            None => write!(f, "at {name} (0x{:x})", self.module_offset),
+        }
+    }
+}
+
+struct ModuleFunctions {
+    imported: u32,
+    bodies: Vec<Range<u64>>,
+    names: ModuleNames,
+}
+
+impl ModuleFunctions {
+    fn parse(wasm: &[u8]) -> PluginResult<Self> {
+        let mut functions = Self {
+            imported: 0,
+            bodies: Vec::new(),
+            names: ModuleNames::default(),
+        };
+
+        for payload in Parser::new(0).parse_all(wasm) {
+            let payload = payload.map_err(parse_error)?;
+
+            match payload {
+                Payload::ImportSection(reader) => {
+                    for import in reader.into_imports().flatten() {
+                        if matches!(import.ty, TypeRef::Func(_) | TypeRef::FuncExact(_)) {
+                            functions.imported += 1;
+                        }
+                    }
+                }
+                Payload::CodeSectionEntry(body) => functions.bodies.push(body.range()),
+                Payload::CustomSection(reader) => functions.names.read(&reader),
+                _ => {}
+            }
+        }
+
+        Ok(functions)
+    }
+
+    fn body_range(&self, func_index: u32) -> Option<&Range<u64>> {
+        func_index
+            .checked_sub(self.imported)
+            .and_then(|i| self.bodies.get(i as usize))
+    }
+
+    fn function_at(&self, offset: u64) -> Option<u32> {
+        self.bodies
+            .iter()
+            .position(|range| range.contains(&offset))
+            .map(|i| self.imported + i as u32)
+    }
+}
+
+/// Maps raw wasm trap frames back to function names and TypeScript locations.
+pub struct Symbolicator {
+    functions: ModuleFunctions,
+    source_map: Option<SourceMap>,
+}
+
+impl Symbolicator {
+    pub fn new(wasm: &[u8], source_map: Option<SourceMap>) -> PluginResult<Self> {
+        Ok(Self {
+            functions: ModuleFunctions::parse(wasm)?,
+            source_map,
+        })
+    }
+
+    /// Loads a module and its source map. Without an explicit map path,
+    /// `<wasm>.map` is used when it exists, matching asc's `--sourceMap` output.
+    pub fn from_files(
+        wasm_path: impl AsRef<Path>,
+        source_map_path: Option<&Path>,
+    ) -> PluginResult<Self> {
+        let wasm = fs::read(&wasm_path).map_err(PluginError::Io)?;
+
+        let source_map = match source_map_path {
+            Some(path) => Some(SourceMap::from_file(path)?),
+            None => {
+                let default_path = PathResolver::append_suffix(wasm_path, ".map");
+                if default_path.exists() {
+                    Some(SourceMap::from_file(&default_path)?)
+                } else {
+                    None
+                }
+            }
+        };
+
+        Self::new(&wasm, source_map)
+    }
+
+    pub fn resolve(&self, frame: &RawFrame) -> Option<SymbolicatedFrame> {
+        let (func_index, module_offset) = match frame.offset {
+            FrameOffset::Module(offset) => (
+                frame
+                    .func_index
+                    .or_else(|| self.functions.function_at(offset)),
+                offset,
+            ),
+            FrameOffset::Function(offset) => {
+                let func_index = frame.func_index?;
+                let body = self.functions.body_range(func_index)?;
+                (Some(func_index), body.start + offset)
+            }
+        };
+
+        Some(SymbolicatedFrame {
+            func_index,
+            function_name: func_index
+                .and_then(|idx| self.functions.names.functions.get(&idx).cloned()),
+            module_offset,
+            location: self
+                .source_map
+                .as_ref()
+                .and_then(|map| map.lookup(module_offset)),
+        })
+    }
+
+    /// Rewrites every recognised frame in `trace`, keeping the original
+    /// indentation and passing through lines that are not frames.
+    pub fn symbolicate(&self, trace: &str) -> String {
+        let mut output = String::new();
+
+        for line in trace.lines() {
+            let resolved = parse_frame(line).and_then(|frame| self.resolve(&frame));
+            match resolved {
+                Some(frame) => {
+                    let indent = &line[..line.len() - line.trim_start().len()];
+                    output.push_str(&format!("{indent}{frame}\n"));
+                }
+                None => {
+                    output.push_str(line);
+                    output.push('\n');
+                }
+            }
+        }
+
+        output
+    }
+}
+
+pub fn symbolicate_trace(
+    wasm_path: impl AsRef<Path>,
+    source_map_path: Option<&Path>,
+    trace: &str,
+) -> PluginResult<String> {
+    let symbolicator = Symbolicator::from_files(wasm_path, source_map_path)?;
+    Ok(symbolicator.symbolicate(trace))
+}
+
+/// Recognises V8 (`wasm-function[12]:0x1a3`), wasmtime (`0x1a3 - <wasm function 12>`)
+/// and plain `func[12]+0x1c` frames.
+pub fn parse_frame(line: &str) -> Option<RawFrame> {
+    if let Some(rest) = after(line, "wasm-function[") {
+        let (index, rest) = split_number(rest, 10)?;
+        let offset = after(rest, "]:0x").and_then(|r| split_number(r, 16))?.0;
+        return Some(RawFrame {
+            func_index: Some(index as u32),
+            offset: FrameOffset::Module(offset),
+        });
+    }
+
+    if let Some(rest) = after(line, "func[") {
+        let (index, rest) = split_number(rest, 10)?;
+        let offset = rest
+            .strip_prefix("]+0x")
+            .and_then(|r| split_number(r, 16))?
+            .0;
+        return Some(RawFrame {
+            func_index: Some(index as u32),
+            offset: FrameOffset::Function(offset),
+        });
+    }
+
+    let trimmed = line.trim_start();
+    let (_, rest) = split_number(trimmed, 10)?;
+    let rest = rest.strip_prefix(':')?.trim_start().strip_prefix("0x")?;
+    let (offset, rest) = split_number(rest, 16)?;
+    rest.trim_start().strip_prefix('-')?;
+
+    let func_index = after(rest, "<wasm function ")
+        .and_then(|r| split_number(r, 10))
+        .map(|(idx, _)| idx as u32);
+
+    Some(RawFrame {
+        func_index,
+        offset: FrameOffset::Module(offset),
+    })
+}
+
+fn after<'a>(haystack: &'a str, needle: &str) -> Option<&'a str> {
+    haystack
+        .find(needle)
+        .map(|pos| &haystack[pos + needle.len()..])
+}
+
+fn split_number(s: &str, radix: u32) -> Option<(u64, &str)> {
+    let end = s.find(|c: char| !c.is_digit(radix)).unwrap_or(s.len());
+    if end == 0 {
+        return None;
+    }
+    let value = u64::from_str_radix(&s[..end], radix).ok()?;
+    Some((value, &s[end..]))
+}
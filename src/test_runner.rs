@@ -0,0 +1,717 @@
+use crate::{install, package_manager, workspace};
+use crate::{BuildConfig, CommandExecutor, PathResolver, PluginError, PluginResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TestFramework {
+    AsPect,
+    Unittest,
+}
+
+impl TestFramework {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestFramework::AsPect => "as-pect",
+            TestFramework::Unittest => "@assemblyscript/unittest",
+        }
+    }
+
+    /// Binary to execute when the project has no `test` script.
+    fn binary(&self) -> Option<&'static str> {
+        match self {
+            TestFramework::AsPect => Some("asp"),
+            TestFramework::Unittest => None,
+        }
+    }
+}
+
+impl fmt::Display for TestFramework {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TestFailure {
+    pub message: String,
+    /// `file:line:column` of the failing assertion, when the output includes one.
+    pub location: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TestCase {
+    pub name: String,
+    pub status: TestStatus,
+    pub duration_ms: Option<f64>,
+    pub failure: Option<TestFailure>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TestSuite {
+    pub name: String,
+    pub file: Option<String>,
+    pub duration_ms: Option<f64>,
+    pub tests: Vec<TestCase>,
+}
+
+impl TestSuite {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            file: None,
+            duration_ms: None,
+            tests: Vec::new(),
+        }
+    }
+
+    fn count(&self, status: TestStatus) -> usize {
+        self.tests.iter().filter(|t| t.status == status).count()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TestReport {
+    pub framework: TestFramework,
+    pub suites: Vec<TestSuite>,
+    pub duration_ms: f64,
+    pub exit_code: Option<i32>,
+    /// Raw runner output, kept so failures that could not be parsed are not lost.
+    pub output: String,
+}
+
+impl TestReport {
+    pub fn total(&self) -> usize {
+        self.suites.iter().map(|s| s.tests.len()).sum()
+    }
+
+    pub fn passed(&self) -> usize {
+        self.count(TestStatus::Passed)
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(TestStatus::Failed)
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.count(TestStatus::Skipped)
+    }
+
+    fn count(&self, status: TestStatus) -> usize {
+        self.suites.iter().map(|s| s.count(status)).sum()
+    }
+
+    /// A run succeeds when the runner exited cleanly and no test failed.
+    pub fn is_success(&self) -> bool {
+        self.exit_code == Some(0) && self.failed() == 0
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = (&TestSuite, &TestCase)> {
+        self.suites.iter().flat_map(|suite| {
+            suite
+                .tests
+                .iter()
+                .filter(|t| t.status == TestStatus::Failed)
+                .map(move |t| (suite, t))
+        })
+    }
+
+    pub fn to_junit_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{}\">\n",
+            xml_escape(self.framework.name()),
+            self.total(),
+            self.failed(),
+            self.skipped(),
+            seconds(Some(self.duration_ms)),
+        ));
+
+        for suite in &self.suites {
+            xml.push_str(&format!(
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{}\"",
+                xml_escape(&suite.name),
+                suite.tests.len(),
+                suite.count(TestStatus::Failed),
+                suite.count(TestStatus::Skipped),
+                seconds(suite.duration_ms),
+            ));
+            if let Some(file) = &suite.file {
+                xml.push_str(&format!(" file=\"{}\"", xml_escape(file)));
+            }
+            xml.push_str(">\n");
+
+            for test in &suite.tests {
+                xml.push_str(&format!(
+                    "    <testcase name=\"{}\" classname=\"{}\" time=\"{}\"",
+                    xml_escape(&test.name),
+                    xml_escape(&suite.name),
+                    seconds(test.duration_ms),
+                ));
+                match (&test.status, &test.failure) {
+                    (TestStatus::Failed, failure) => {
+                        let message = failure
+                            .as_ref()
+                            .map(|f| f.message.as_str())
+                            .unwrap_or("failed");
+                        let mut body = message.to_string();
+                        if let Some(location) = failure.as_ref().and_then(|f| f.location.as_ref()) {
+                            if !message.contains(location.as_str()) {
+                                body.push_str(&format!("\nat {location}"));
+                            }
+                        }
+                        xml.push_str(&format!(
+                            ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                            xml_escape(message.lines().next().unwrap_or_default()),
+                            xml_escape(&body),
+                        ));
+                    }
+                    (TestStatus::Skipped, _) => {
+                        xml.push_str(">\n      <skipped/>\n    </testcase>\n");
+                    }
+                    (TestStatus::Passed, _) => xml.push_str("/>\n"),
+                }
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+
+    pub fn write_junit(&self, path: impl AsRef<Path>) -> PluginResult<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                PathResolver::ensure_output_directory(parent)?;
+            }
+        }
+        fs::write(path, self.to_junit_xml()).map_err(PluginError::Io)
+    }
+}
+
+impl fmt::Display for TestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for suite in &self.suites {
+            writeln!(f, "{}", suite.name)?;
+            for test in &suite.tests {
+                let mark = match test.status {
+                    TestStatus::Passed => "✅",
+                    TestStatus::Failed => "❌",
+                    TestStatus::Skipped => "⏭️ ",
+                };
+                writeln!(f, "  {mark} {}", test.name)?;
+                if let Some(failure) = &test.failure {
+                    writeln!(f, "       {}", failure.message.replace('\n', "\n       "))?;
+                    if let Some(location) = failure
+                        .location
+                        .as_ref()
+                        .filter(|l| !failure.message.contains(l.as_str()))
+                    {
+                        writeln!(f, "       at {location}")?;
+                    }
+                }
+            }
+        }
+        write!(
+            f,
+            "{} passed, {} failed, {} skipped, {} total ({:.0}ms)",
+            self.passed(),
+            self.failed(),
+            self.skipped(),
+            self.total(),
+            self.duration_ms
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TestOptions {
+    /// Extra arguments forwarded to the test runner.
+    pub args: Vec<String>,
+}
+
+/// Detects the test framework from `package.json` dependencies.
+pub fn detect_framework(project_path: impl AsRef<Path>) -> Option<TestFramework> {
+    let project_path = project_path.as_ref();
+    let package = workspace::read_package_json(project_path)?;
+    let has_dependency = |name: &str| {
+        ["dependencies", "devDependencies"]
+            .iter()
+            .any(|section| package[section].get(name).is_some())
+    };
+
+    if has_dependency("@as-pect/cli") || has_dependency("@as-pect/core") {
+        Some(TestFramework::AsPect)
+    } else if has_dependency("@assemblyscript/unittest") {
+        Some(TestFramework::Unittest)
+    } else if project_path.join("as-pect.config.js").exists() {
+        Some(TestFramework::AsPect)
+    } else {
+        None
+    }
+}
+
+/// Runs the project's tests through its package manager and parses the output.
+///
+/// The `test` script is preferred; without one, the framework's own binary is
+/// executed.
+pub fn run_tests(config: &BuildConfig, options: &TestOptions) -> PluginResult<TestReport> {
+    let framework =
+        detect_framework(&config.project_path).ok_or_else(|| PluginError::InvalidProjectStructure {
+            reason: "No AssemblyScript test framework found in package.json (expected @as-pect/cli or @assemblyscript/unittest)".to_string(),
+        })?;
+
+    let package_manager = package_manager::detect(&config.project_path)?;
+    install::ensure_dependencies(config, &package_manager)?;
+    let has_test_script = workspace::read_package_json(&config.project_path)
+        .map(|package| package["scripts"]["test"].is_string())
+        .unwrap_or(false);
+
+    let (cwd, args) = if has_test_script {
+        workspace::script_command(
+            &config.project_path,
+            &package_manager,
+            "test",
+            &options.args,
+        )
+    } else {
+        let binary = framework
+            .binary()
+            .ok_or_else(|| PluginError::ScriptNotFound {
+                script: "test".to_string(),
+                package_json: config
+                    .project_path
+                    .join("package.json")
+                    .display()
+                    .to_string(),
+            })?;
+        (
+            config.project_path.clone(),
+            package_manager.exec_args(binary, &options.args),
+        )
+    };
+    let package_manager = package_manager.command();
+
+    eprintln!("🧪 Running {framework} tests with {package_manager}...");
+
+    let started = Instant::now();
+    let output = CommandExecutor::execute_command(package_manager, &args, &cwd, config.verbose)?;
+    let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    let text = strip_ansi(&format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    ));
+
+    Ok(TestReport {
+        framework,
+        suites: parse_output(framework, &text),
+        duration_ms,
+        exit_code: output.status.code(),
+        output: text,
+    })
+}
+
+/// Parses runner output. as-pect's default reporter is recognised by its
+/// `[Describe]:` markers; anything else is read as TAP.
+pub fn parse_output(framework: TestFramework, output: &str) -> Vec<TestSuite> {
+    if framework == TestFramework::AsPect || output.contains("[Describe]:") {
+        parse_aspect(output)
+    } else {
+        parse_tap(output)
+    }
+}
+
+fn parse_aspect(output: &str) -> Vec<TestSuite> {
+    let mut suites: Vec<TestSuite> = Vec::new();
+    // Suites reported since the last `[File]:` summary line.
+    let mut pending_from = 0;
+
+    for line in output.lines() {
+        let Some((tag, value)) = aspect_tag(line) else {
+            if let Some(test) = last_failed_test(&mut suites) {
+                append_failure_detail(test, line.trim());
+            }
+            continue;
+        };
+
+        match tag {
+            "Describe" => suites.push(TestSuite::new(value)),
+            "Success" | "Fail" | "Todo" => {
+                if suites.is_empty() {
+                    suites.push(TestSuite::new("<root>"));
+                }
+                let name = value
+                    .trim_start_matches(['✔', '✖', '!', ' '])
+                    .split("RTrace:")
+                    .next()
+                    .unwrap_or_default()
+                    .trim();
+                let status = match tag {
+                    "Success" => TestStatus::Passed,
+                    "Fail" => TestStatus::Failed,
+                    _ => TestStatus::Skipped,
+                };
+                let failure = (status == TestStatus::Failed).then(|| TestFailure {
+                    message: String::new(),
+                    location: None,
+                });
+                if let Some(suite) = suites.last_mut() {
+                    suite.tests.push(TestCase {
+                        name: name.to_string(),
+                        status,
+                        duration_ms: None,
+                        failure,
+                    });
+                }
+            }
+            "Message" | "Actual" | "Expected" | "Stack" => {
+                if let Some(test) = last_failed_test(&mut suites) {
+                    let detail = if tag == "Message" {
+                        value.to_string()
+                    } else {
+                        format!("{tag}: {value}")
+                    };
+                    append_failure_detail(test, &detail);
+                }
+            }
+            "File" => {
+                for suite in &mut suites[pending_from..] {
+                    suite.file = Some(value.to_string());
+                }
+            }
+            "Time" => {
+                let duration = parse_duration(value);
+                let pending = &mut suites[pending_from..];
+                let count = pending.len().max(1) as f64;
+                for suite in pending {
+                    suite.duration_ms = duration.map(|d| d / count);
+                }
+                pending_from = suites.len();
+            }
+            _ => {}
+        }
+    }
+
+    suites
+}
+
+fn aspect_tag(line: &str) -> Option<(&str, &str)> {
+    let rest = line.trim_start().strip_prefix('[')?;
+    let (tag, value) = rest.split_once("]:")?;
+    (!tag.is_empty() && tag.chars().all(|c| c.is_ascii_alphabetic())).then(|| (tag, value.trim()))
+}
+
+fn last_failed_test(suites: &mut [TestSuite]) -> Option<&mut TestCase> {
+    suites
+        .last_mut()?
+        .tests
+        .last_mut()
+        .filter(|t| t.status == TestStatus::Failed)
+}
+
+fn append_failure_detail(test: &mut TestCase, detail: &str) {
+    if detail.is_empty() {
+        return;
+    }
+    let failure = test.failure.get_or_insert_with(|| TestFailure {
+        message: String::new(),
+        location: None,
+    });
+    if failure.location.is_none() {
+        failure.location = find_location(detail);
+    }
+    if !failure.message.is_empty() {
+        failure.message.push('\n');
+    }
+    failure.message.push_str(detail);
+}
+
+fn parse_tap(output: &str) -> Vec<TestSuite> {
+    let mut suites: Vec<TestSuite> = Vec::new();
+    let mut in_diagnostics = false;
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+
+        if in_diagnostics {
+            if trimmed == "..." {
+                in_diagnostics = false;
+            } else if let Some(test) = last_failed_test(&mut suites) {
+                let detail = trimmed
+                    .strip_prefix("message:")
+                    .map(|m| m.trim().trim_matches(['\'', '"']))
+                    .unwrap_or(trimmed);
+                append_failure_detail(test, detail);
+            }
+            continue;
+        }
+
+        if trimmed == "---" {
+            in_diagnostics = true;
+        } else if let Some(name) = trimmed
+            .strip_prefix("# Subtest:")
+            .or_else(|| trimmed.strip_prefix('#'))
+        {
+            let name = name.trim();
+            if !name.is_empty() && !is_tap_summary(name) {
+                suites.push(TestSuite::new(name));
+            }
+        } else if let Some(test) = parse_tap_result(trimmed) {
+            if suites.is_empty() {
+                suites.push(TestSuite::new("<root>"));
+            }
+            if let Some(suite) = suites.last_mut() {
+                suite.tests.push(test);
+            }
+        }
+    }
+
+    suites.retain(|suite| !suite.tests.is_empty());
+    suites
+}
+
+fn parse_tap_result(line: &str) -> Option<TestCase> {
+    let (passed, rest) = match line.strip_prefix("not ok") {
+        Some(rest) => (false, rest),
+        None => (true, line.strip_prefix("ok")?),
+    };
+
+    let rest = rest.trim_start_matches(|c: char| c.is_ascii_digit() || c == ' ');
+    let rest = rest.strip_prefix('-').unwrap_or(rest).trim();
+    let (name, directive) = match rest.split_once(" # ") {
+        Some((name, directive)) => (name.trim(), Some(directive.to_ascii_lowercase())),
+        None => (rest, None),
+    };
+
+    let skipped = directive
+        .as_deref()
+        .is_some_and(|d| d.starts_with("skip") || d.starts_with("todo"));
+    let status = if skipped {
+        TestStatus::Skipped
+    } else if passed {
+        TestStatus::Passed
+    } else {
+        TestStatus::Failed
+    };
+    let duration_ms = directive
+        .as_deref()
+        .and_then(|d| d.strip_prefix("time="))
+        .and_then(parse_duration);
+
+    Some(TestCase {
+        name: name.to_string(),
+        status,
+        duration_ms,
+        failure: (status == TestStatus::Failed).then(|| TestFailure {
+            message: String::new(),
+            location: None,
+        }),
+    })
+}
+
+fn is_tap_summary(comment: &str) -> bool {
+    [
+        "tests ", "pass ", "fail ", "skip ", "todo ", "duration", "time=",
+    ]
+    .iter()
+    .any(|prefix| comment.starts_with(prefix))
+}
+
+/// Finds the first `file.ts:line:column` or `file.ts(line:column)` reference.
+fn find_location(text: &str) -> Option<String> {
+    for (index, _) in text.match_indices(".ts") {
+        let start = text[..index]
+            .char_indices()
+            .rev()
+            .find(|&(_, c)| c.is_whitespace() || matches!(c, '(' | '"' | '\''))
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(0);
+        let file = &text[start..index + 3];
+        let rest = &text[index + 3..];
+
+        let position = rest
+            .strip_prefix(':')
+            .or_else(|| rest.strip_prefix('('))
+            .map(|p| {
+                p.split(|c: char| !c.is_ascii_digit() && c != ':')
+                    .next()
+                    .unwrap_or_default()
+                    .trim_end_matches(':')
+            });
+        if let Some(position) = position.filter(|p| !p.is_empty()) {
+            return Some(format!("{file}:{position}"));
+        }
+    }
+    None
+}
+
+fn parse_duration(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    match unit.trim() {
+        "" | "ms" => Some(number),
+        "s" => Some(number * 1000.0),
+        "us" | "μs" | "µs" => Some(number / 1000.0),
+        _ => None,
+    }
+}
+
+pub(crate) fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn seconds(duration_ms: Option<f64>) -> String {
+    format!("{:.3}", duration_ms.unwrap_or(0.0) / 1000.0)
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_location, parse_duration, parse_output, TestFramework, TestStatus};
+
+    #[test]
+    fn parses_tap_results_directives_and_diagnostics() {
+        let output = "\
+TAP version 13
+# Subtest: math
+ok 1 - adds # time=1.5ms
+not ok 2 - divides
+  ---
+  message: 'expected 2 got 3'
+  at: assembly/__tests__/math.spec.ts:8:5
+  ...
+ok 3 - rounds # SKIP not yet
+# tests 3
+# pass 1
+";
+        let suites = parse_output(TestFramework::Unittest, output);
+        assert_eq!(suites.len(), 1);
+        let suite = &suites[0];
+        assert_eq!(suite.name, "math");
+        let statuses: Vec<_> = suite.tests.iter().map(|t| t.status).collect();
+        assert_eq!(
+            statuses,
+            [TestStatus::Passed, TestStatus::Failed, TestStatus::Skipped]
+        );
+        assert_eq!(suite.tests[0].name, "adds");
+        assert_eq!(suite.tests[0].duration_ms, Some(1.5));
+
+        let failure = suite.tests[1].failure.as_ref().unwrap();
+        assert!(failure.message.starts_with("expected 2 got 3"));
+        assert_eq!(
+            failure.location.as_deref(),
+            Some("assembly/__tests__/math.spec.ts:8:5")
+        );
+    }
+
+    #[test]
+    fn tap_results_without_a_subtest_go_to_root() {
+        let suites = parse_output(TestFramework::Unittest, "ok 1 works\n# tests 1\n");
+        assert_eq!(suites.len(), 1);
+        assert_eq!(suites[0].name, "<root>");
+        assert_eq!(suites[0].tests[0].name, "works");
+    }
+
+    #[test]
+    fn parses_aspect_reporter_output() {
+        let output = "\
+[Describe]: strings
+ [Success]: ✔ concatenates RTrace: +2
+    [Fail]: ✖ splits
+ [Message]: lengths differ
+  [Actual]: 2
+[Expected]: 3
+    [Todo]: ! trims
+    [File]: assembly/__tests__/strings.spec.ts
+    [Time]: 4ms
+";
+        let suites = parse_output(TestFramework::AsPect, output);
+        assert_eq!(suites.len(), 1);
+        let suite = &suites[0];
+        assert_eq!(suite.name, "strings");
+        assert_eq!(
+            suite.file.as_deref(),
+            Some("assembly/__tests__/strings.spec.ts")
+        );
+        assert_eq!(suite.duration_ms, Some(4.0));
+
+        let names: Vec<_> = suite.tests.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["concatenates", "splits", "trims"]);
+        assert_eq!(suite.tests[2].status, TestStatus::Skipped);
+        let failure = suite.tests[1].failure.as_ref().unwrap();
+        assert_eq!(failure.message, "lengths differ\nActual: 2\nExpected: 3");
+    }
+
+    #[test]
+    fn parses_durations_in_each_unit() {
+        assert_eq!(parse_duration("12"), Some(12.0));
+        assert_eq!(parse_duration("1.5s"), Some(1500.0));
+        assert_eq!(parse_duration("250us"), Some(0.25));
+        assert_eq!(parse_duration("3 minutes"), None);
+    }
+
+    #[test]
+    fn finds_colon_and_parenthesized_locations() {
+        assert_eq!(
+            find_location("at add (assembly/index.ts:4:10)").as_deref(),
+            Some("assembly/index.ts:4:10")
+        );
+        assert_eq!(
+            find_location("abort: boom in assembly/index.ts(12:3)").as_deref(),
+            Some("assembly/index.ts:12:3")
+        );
+        assert_eq!(find_location("no location in index.ts here"), None);
+    }
+
+    #[test]
+    fn multi_byte_separator_before_file_does_not_panic() {
+        assert_eq!(
+            find_location("失敗\u{3000}assembly/index.ts:7:1").as_deref(),
+            Some("assembly/index.ts:7:1")
+        );
+        assert_eq!(
+            find_location("\u{a0}tests/日本.ts:1:2").as_deref(),
+            Some("tests/日本.ts:1:2")
+        );
+    }
+}
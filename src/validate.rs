@@ -0,0 +1,167 @@
+use crate::config::FeaturesConfig;
+use crate::{PluginError, PluginResult};
+use std::fs;
+use std::path::Path;
+use wasmparser::{Validator, WasmFeatures};
+
+/// Features asc turns on without any `--enable` flags.
+pub const ASC_DEFAULT_FEATURES: &[&str] = &[
+    "mutable-globals",
+    "sign-extension",
+    "nontrapping-f2i",
+    "bulk-memory",
+    "simd",
+    "reference-types",
+    "multi-value",
+];
+
+/// Maps an asc feature name to the wasmparser features it needs.
+pub fn wasm_features_for(feature: &str) -> Option<WasmFeatures> {
+    let features = match feature {
+        "mutable-globals" => WasmFeatures::MUTABLE_GLOBAL,
+        "sign-extension" => WasmFeatures::SIGN_EXTENSION,
+        "nontrapping-f2i" => WasmFeatures::SATURATING_FLOAT_TO_INT,
+        "bulk-memory" => WasmFeatures::BULK_MEMORY,
+        "simd" => WasmFeatures::SIMD,
+        "relaxed-simd" => WasmFeatures::SIMD | WasmFeatures::RELAXED_SIMD,
+        "threads" => WasmFeatures::THREADS,
+        "reference-types" => WasmFeatures::REFERENCE_TYPES,
+        "multi-value" => WasmFeatures::MULTI_VALUE,
+        "tail-calls" => WasmFeatures::TAIL_CALL,
+        "exception-handling" => WasmFeatures::EXCEPTIONS,
+        "gc" => {
+            WasmFeatures::GC | WasmFeatures::FUNCTION_REFERENCES | WasmFeatures::REFERENCE_TYPES
+        }
+        "memory64" => WasmFeatures::MEMORY64,
+        "multi-memory" => WasmFeatures::MULTI_MEMORY,
+        "extended-const" => WasmFeatures::EXTENDED_CONST,
+        _ => return None,
+    };
+    Some(features)
+}
+
+/// The feature set a module built with `config` may use.
+pub fn allowed_features(config: &FeaturesConfig) -> PluginResult<WasmFeatures> {
+    let mut allowed = WasmFeatures::MVP;
+
+    let enabled = ASC_DEFAULT_FEATURES
+        .iter()
+        .map(|f| f.to_string())
+        .chain(config.enable.iter().cloned())
+        .filter(|f| !config.disable.contains(f));
+
+    for feature in enabled {
+        allowed |= wasm_features_for(&feature).ok_or_else(|| PluginError::InvalidConfig {
+            path: crate::config::CONFIG_FILE_NAME.to_string(),
+            reason: format!("unknown WebAssembly feature '{feature}'"),
+        })?;
+    }
+
+    Ok(allowed)
+}
+
+/// Features `asconfig.json` turns on and off for `target`: its top-level
+/// `options` merged with the target's own. asc reads the file for direct
+/// and script builds alike. Names asc itself would reject are left out.
+pub fn asconfig_features(project_path: impl AsRef<Path>, target: &str) -> FeaturesConfig {
+    let mut features = FeaturesConfig::default();
+    let Some(asconfig) = fs::read_to_string(project_path.as_ref().join("asconfig.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+    else {
+        return features;
+    };
+
+    for options in [&asconfig["options"], &asconfig["targets"][target]] {
+        features.enable.extend(feature_names(&options["enable"]));
+        features.disable.extend(feature_names(&options["disable"]));
+    }
+    features
+}
+
+/// `["threads", "simd"]` or asc's comma-separated `"threads,simd"`.
+fn feature_names(value: &serde_json::Value) -> Vec<String> {
+    let names: Vec<&str> = match value {
+        serde_json::Value::String(list) => list.split(',').collect(),
+        serde_json::Value::Array(items) => items.iter().filter_map(|v| v.as_str()).collect(),
+        _ => Vec::new(),
+    };
+    names
+        .into_iter()
+        .map(str::trim)
+        .filter(|name| wasm_features_for(name).is_some())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Checks section order, types and instruction validity, and rejects any use
+/// of a feature that is not enabled.
+pub fn validate_module(
+    wasm: &[u8],
+    path: impl AsRef<Path>,
+    features: WasmFeatures,
+) -> PluginResult<()> {
+    Validator::new_with_features(features)
+        .validate_all(wasm)
+        .map(|_| ())
+        .map_err(|e| PluginError::InvalidWasm {
+            path: path.as_ref().display().to_string(),
+            offset: e.offset(),
+            reason: e.message().to_string(),
+        })
+}
+
+pub fn validate_file(path: impl AsRef<Path>, features: WasmFeatures) -> PluginResult<()> {
+    let path = path.as_ref();
+    let wasm = fs::read(path).map_err(PluginError::Io)?;
+    validate_module(&wasm, path, features)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{allowed_features, asconfig_features, FeaturesConfig};
+    use std::fs;
+    use wasmparser::WasmFeatures;
+
+    #[test]
+    fn asconfig_enables_merge_into_allowed_features() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("asconfig.json"),
+            r#"{
+                "options": { "enable": ["threads"], "disable": "simd" },
+                "targets": {
+                    "release": { "enable": "tail-calls,bogus" },
+                    "debug": { "enable": ["gc"] }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let asconfig = asconfig_features(dir.path(), "release");
+        assert_eq!(asconfig.enable, ["threads", "tail-calls"]);
+        assert_eq!(asconfig.disable, ["simd"]);
+
+        let mut config = FeaturesConfig {
+            enable: vec!["memory64".to_string()],
+            disable: Vec::new(),
+        };
+        config.enable.extend(asconfig.enable);
+        config.disable.extend(asconfig.disable);
+        let allowed = allowed_features(&config).unwrap();
+        assert!(allowed.contains(WasmFeatures::THREADS));
+        assert!(allowed.contains(WasmFeatures::TAIL_CALL));
+        assert!(allowed.contains(WasmFeatures::MEMORY64));
+        assert!(!allowed.contains(WasmFeatures::SIMD));
+        assert!(!allowed.contains(WasmFeatures::GC));
+    }
+
+    #[test]
+    fn missing_asconfig_adds_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            asconfig_features(dir.path(), "release"),
+            FeaturesConfig::default()
+        );
+    }
+}
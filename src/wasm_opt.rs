@@ -0,0 +1,142 @@
+use crate::config::WasmOptConfig;
+use crate::{inspect, validate, workspace};
+use crate::{BuildConfig, CommandExecutor, PathResolver, PluginError, PluginResult};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use wasmparser::WasmFeatures;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OptimizationReport {
+    pub tool: String,
+    pub passes: Vec<String>,
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+/// Finds wasm-opt: the configured path, the project's or workspace's `node_modules/.bin`
+/// (Binaryen ships with AssemblyScript), then `PATH`.
+pub fn resolve_wasm_opt(project_path: impl AsRef<Path>, config: &WasmOptConfig) -> Option<PathBuf> {
+    if let Some(path) = &config.path {
+        return CommandExecutor::is_tool_installed(path).then(|| PathBuf::from(path));
+    }
+
+    if let Some(local) = workspace::find_bin(project_path, "wasm-opt") {
+        if CommandExecutor::is_tool_installed(&local) {
+            return Some(local);
+        }
+    }
+
+    CommandExecutor::is_tool_installed("wasm-opt").then(|| PathBuf::from("wasm-opt"))
+}
+
+/// Runs wasm-opt over `wasm_path` in place.
+///
+/// Returns `Ok(None)` when the stage is disabled, has no passes for the
+/// configured level, or wasm-opt cannot be found. The original module is only
+/// replaced once the optimized output has been validated.
+pub fn optimize(
+    wasm_path: impl AsRef<Path>,
+    build_config: &BuildConfig,
+    config: &WasmOptConfig,
+    features: WasmFeatures,
+) -> PluginResult<Option<OptimizationReport>> {
+    let passes = config.passes(&build_config.optimization_level);
+    if !config.enabled || passes.is_empty() {
+        return Ok(None);
+    }
+
+    let tool = match resolve_wasm_opt(&build_config.project_path, config) {
+        Some(tool) => tool,
+        None => {
+            if build_config.verbose {
+                eprintln!("⚠️  wasm-opt not found, skipping post-build optimization");
+            }
+            return Ok(None);
+        }
+    };
+
+    let wasm_path = wasm_path.as_ref();
+    let original = fs::read(wasm_path).map_err(PluginError::Io)?;
+    let summary = inspect::inspect_module(&original)?;
+
+    let optimized_path = PathResolver::append_suffix(wasm_path, ".opt");
+    let source_map = PathResolver::append_suffix(wasm_path, ".map");
+    let optimized_map = PathResolver::append_suffix(&optimized_path, ".map");
+    let has_source_map = source_map.exists();
+
+    let mut args: Vec<OsString> =
+        vec![wasm_path.into(), "-o".into(), optimized_path.clone().into()];
+    args.extend(
+        summary
+            .features
+            .iter()
+            .filter_map(|f| binaryen_feature_flag(f))
+            .map(OsString::from),
+    );
+    if has_source_map {
+        args.extend([
+            "--input-source-map".into(),
+            source_map.clone().into(),
+            "--output-source-map".into(),
+            optimized_map.clone().into(),
+        ]);
+    }
+    args.extend(passes.iter().map(OsString::from));
+
+    eprintln!("🔧 Optimizing with wasm-opt {}...", passes.join(" "));
+
+    let output = CommandExecutor::execute_command(
+        &tool,
+        &args,
+        &build_config.project_path,
+        build_config.verbose,
+    )?;
+
+    if !output.status.success() {
+        let _ = fs::remove_file(&optimized_path);
+        let _ = fs::remove_file(&optimized_map);
+        return Err(CommandExecutor::command_error("wasm-opt", &output));
+    }
+
+    let optimized = fs::read(&optimized_path).map_err(PluginError::Io)?;
+    if let Err(e) = validate::validate_module(&optimized, &optimized_path, features) {
+        let _ = fs::remove_file(&optimized_path);
+        let _ = fs::remove_file(&optimized_map);
+        return Err(e);
+    }
+
+    fs::rename(&optimized_path, wasm_path).map_err(PluginError::Io)?;
+    if has_source_map && Path::new(&optimized_map).exists() {
+        fs::rename(&optimized_map, &source_map).map_err(PluginError::Io)?;
+    }
+
+    Ok(Some(OptimizationReport {
+        tool: tool.display().to_string(),
+        passes: passes.to_vec(),
+        size_before: original.len() as u64,
+        size_after: optimized.len() as u64,
+    }))
+}
+
+/// Translates asc feature names, as reported by the inspector, to wasm-opt flags.
+fn binaryen_feature_flag(feature: &str) -> Option<&'static str> {
+    match feature {
+        "sign-extension" => Some("--enable-sign-ext"),
+        "mutable-globals" => Some("--enable-mutable-globals"),
+        "nontrapping-f2i" => Some("--enable-nontrapping-float-to-int"),
+        "bulk-memory" => Some("--enable-bulk-memory"),
+        "simd" => Some("--enable-simd"),
+        "relaxed-simd" => Some("--enable-relaxed-simd"),
+        "threads" => Some("--enable-threads"),
+        "exception-handling" => Some("--enable-exception-handling"),
+        "tail-calls" => Some("--enable-tail-call"),
+        "reference-types" => Some("--enable-reference-types"),
+        "multi-value" => Some("--enable-multivalue"),
+        "gc" => Some("--enable-gc"),
+        "memory64" => Some("--enable-memory64"),
+        "multi-memory" => Some("--enable-multimemory"),
+        _ => None,
+    }
+}
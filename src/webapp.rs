@@ -0,0 +1,154 @@
+use crate::{run, workspace, BuildResult, PluginResult};
+use std::fs;
+use std::path::Path;
+
+pub const INDEX_HTML: &str = "index.html";
+pub const LOADER_JS: &str = "loader.js";
+
+/// Browser module code defining `instantiate()`. Goes through asc's ESM
+/// bindings when the build has them, otherwise instantiates the module with
+/// asc's default `env` imports. Shared with the playground page.
+const INSTANTIATE: &str = r#"const wasmUrl = new URL({wasm}, import.meta.url);
+const bindingsUrl = {bindings};
+
+class Abort extends Error {
+  constructor(message, file, line, column) {
+    super(`${message ?? "abort"}${file ? ` at ${file}:${line}:${column}` : ""}`);
+    Object.assign(this, { file, line, column });
+  }
+}
+
+let memory;
+const liftString = (ptr) => {
+  if (!ptr || !memory) return null;
+  const end = (ptr + new Uint32Array(memory.buffer)[(ptr - 4) >>> 2]) >>> 1;
+  const units = new Uint16Array(memory.buffer);
+  let start = ptr >>> 1, out = "";
+  while (end - start > 1024) out += String.fromCharCode(...units.subarray(start, (start += 1024)));
+  return out + String.fromCharCode(...units.subarray(start, end));
+};
+
+async function instantiate() {
+  if (bindingsUrl) {
+    return { ...(await import(bindingsUrl)) };
+  }
+  const response = await fetch(wasmUrl);
+  const module = await WebAssembly.compile(await response.arrayBuffer());
+  const imports = {
+    env: {
+      abort(msg, file, line, column) {
+        throw new Abort(liftString(msg), liftString(file), line, column);
+      },
+      trace(msg, n, ...args) {
+        console.log(`trace: ${liftString(msg)}${n ? " " + args.slice(0, n).join(", ") : ""}`);
+      },
+      seed: () => Date.now(),
+    },
+  };
+  for (const imp of WebAssembly.Module.imports(module)) {
+    imports[imp.module] ??= {};
+    if (imp.kind === "function" && !(imp.name in imports[imp.module])) {
+      imports[imp.module][imp.name] = () => {
+        throw new Error(`unresolved import ${imp.module}.${imp.name}`);
+      };
+    }
+  }
+  const instance = await WebAssembly.instantiate(module, imports);
+  memory = instance.exports.memory;
+  return instance.exports;
+}
+"#;
+
+const LOADER: &str = r#"{instantiate}
+export const exports = await instantiate();
+window.wasmExports = exports;
+window.dispatchEvent(new CustomEvent("wasmasc:ready", { detail: exports }));
+"#;
+
+const INDEX: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <meta name="viewport" content="width=device-width, initial-scale=1">
+  <title>{title}</title>
+</head>
+<body>
+  <h1>{title}</h1>
+  <p>Exports are available as <code>window.wasmExports</code>:</p>
+  <ul id="exports"><li>Loading…</li></ul>
+  <script type="module">
+    const list = document.getElementById("exports");
+    try {
+      const { exports } = await import("./loader.js");
+      list.replaceChildren(...Object.entries(exports).map(([name, value]) => {
+        const item = document.createElement("li");
+        item.textContent = typeof value === "function" ? `${name}()` : name;
+        return item;
+      }));
+    } catch (e) {
+      list.innerHTML = "";
+      list.append(Object.assign(document.createElement("li"), { textContent: String(e) }));
+      throw e;
+    }
+  </script>
+</body>
+</html>
+"#;
+
+/// Writes `index.html` and `loader.js` next to the result's module and adds
+/// them to `additional_files`. The loader puts the module's exports on
+/// `window.wasmExports` and fires `wasmasc:ready` once they are available.
+pub fn generate(result: &mut BuildResult, project_path: impl AsRef<Path>) -> PluginResult<()> {
+    let dir = result.wasm_path.parent().unwrap_or(Path::new("."));
+    let loader = LOADER.replace("{instantiate}", &instantiate_script(result));
+    let loader_path = dir.join(LOADER_JS);
+    fs::write(&loader_path, loader)?;
+
+    let index_path = dir.join(INDEX_HTML);
+    fs::write(
+        &index_path,
+        INDEX.replace("{title}", &escape_html(&title(project_path.as_ref()))),
+    )?;
+
+    result.additional_files.push(loader_path);
+    result.additional_files.push(index_path);
+    Ok(())
+}
+
+/// [`INSTANTIATE`] pointed at the result's module and bindings, which are
+/// expected in the same directory as the page.
+pub(crate) fn instantiate_script(result: &BuildResult) -> String {
+    let relative = |path: &Path| {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        serde_json::Value::from(format!("./{name}")).to_string()
+    };
+    let bindings = result
+        .js_path
+        .clone()
+        .or_else(|| run::find_esm_bindings(&result.wasm_path))
+        .map(|js| format!("new URL({}, import.meta.url)", relative(&js)))
+        .unwrap_or_else(|| "null".to_string());
+    INSTANTIATE
+        .replace("{wasm}", &relative(&result.wasm_path))
+        .replace("{bindings}", &bindings)
+}
+
+/// The package name from `package.json`, else the project directory's name.
+pub(crate) fn title(project_path: &Path) -> String {
+    workspace::read_package_json(project_path)
+        .and_then(|package| package["name"].as_str().map(str::to_string))
+        .or_else(|| {
+            std::path::absolute(project_path)
+                .ok()?
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "AssemblyScript".to_string())
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,467 @@
+use crate::budget::matches_pattern;
+use crate::package_manager::{PackageManager, PackageManagerKind};
+use crate::{BuildConfig, BuildResult, PathResolver, PluginError, PluginResult, WasmBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WorkspacePackage {
+    pub name: String,
+    pub path: PathBuf,
+    /// Other workspace packages this one depends on.
+    pub dependencies: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub packages: Vec<WorkspacePackage>,
+}
+
+impl Workspace {
+    /// Reads the member patterns from `package.json` `workspaces` or
+    /// `pnpm-workspace.yaml` and loads every matching package.
+    pub fn load(root: impl AsRef<Path>) -> PluginResult<Self> {
+        let root = root.as_ref();
+        let patterns =
+            member_patterns(root).ok_or_else(|| PluginError::InvalidProjectStructure {
+                reason: format!("{} is not a workspace root", root.display()),
+            })?;
+
+        let mut included = BTreeSet::new();
+        for pattern in patterns.iter().filter(|p| !p.starts_with('!')) {
+            included.extend(expand_pattern(root, pattern));
+        }
+        for pattern in patterns.iter().filter_map(|p| p.strip_prefix('!')) {
+            for excluded in expand_pattern(root, pattern) {
+                included.remove(&excluded);
+            }
+        }
+
+        let manifests: Vec<(PathBuf, serde_json::Value)> = included
+            .into_iter()
+            .filter_map(|dir| read_package_json(&dir).map(|package| (dir, package)))
+            .collect();
+        let names: BTreeSet<String> = manifests
+            .iter()
+            .filter_map(|(_, package)| package["name"].as_str().map(str::to_string))
+            .collect();
+
+        let packages = manifests
+            .iter()
+            .map(|(dir, package)| {
+                let name = package["name"]
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| {
+                        dir.file_name()
+                            .map(|n| n.to_string_lossy().to_string())
+                            .unwrap_or_default()
+                    });
+                let dependencies = declared_dependencies(package)
+                    .into_iter()
+                    .filter(|dep| names.contains(dep) && *dep != name)
+                    .collect();
+                WorkspacePackage {
+                    name,
+                    path: dir.clone(),
+                    dependencies,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            packages,
+        })
+    }
+
+    /// The package whose directory is `project_path`.
+    pub fn package_at(&self, project_path: impl AsRef<Path>) -> Option<&WorkspacePackage> {
+        let target = canonical(project_path.as_ref());
+        self.packages.iter().find(|p| canonical(&p.path) == target)
+    }
+
+    /// Packages ordered so each comes after the workspace packages it depends on.
+    pub fn build_order<'a>(
+        &'a self,
+        packages: &[&'a WorkspacePackage],
+    ) -> PluginResult<Vec<&'a WorkspacePackage>> {
+        let selected: BTreeMap<&str, &WorkspacePackage> =
+            packages.iter().map(|p| (p.name.as_str(), *p)).collect();
+        let mut order = Vec::new();
+        let mut done = BTreeSet::new();
+        let mut visiting = Vec::new();
+
+        for package in packages {
+            visit(package, &selected, &mut done, &mut visiting, &mut order)?;
+        }
+        Ok(order)
+    }
+}
+
+fn visit<'a>(
+    package: &'a WorkspacePackage,
+    selected: &BTreeMap<&str, &'a WorkspacePackage>,
+    done: &mut BTreeSet<String>,
+    visiting: &mut Vec<String>,
+    order: &mut Vec<&'a WorkspacePackage>,
+) -> PluginResult<()> {
+    if done.contains(&package.name) {
+        return Ok(());
+    }
+    if let Some(start) = visiting.iter().position(|name| *name == package.name) {
+        let mut cycle = visiting[start..].to_vec();
+        cycle.push(package.name.clone());
+        return Err(PluginError::InvalidProjectStructure {
+            reason: format!("Workspace dependency cycle: {}", cycle.join(" -> ")),
+        });
+    }
+
+    visiting.push(package.name.clone());
+    for dep in &package.dependencies {
+        if let Some(dep) = selected.get(dep.as_str()) {
+            visit(dep, selected, done, visiting, order)?;
+        }
+    }
+    visiting.pop();
+
+    done.insert(package.name.clone());
+    order.push(package);
+    Ok(())
+}
+
+/// The nearest directory at or above `project_path` that declares workspaces.
+pub fn find_root(project_path: impl AsRef<Path>) -> Option<PathBuf> {
+    let start = canonical(project_path.as_ref());
+    start
+        .ancestors()
+        .find(|dir| member_patterns(dir).is_some())
+        .map(Path::to_path_buf)
+}
+
+/// The directory holding `package.json`, the lockfile and `node_modules`:
+/// the workspace root when `project_path` is inside one, else the project.
+pub fn install_root(project_path: impl AsRef<Path>) -> PathBuf {
+    let project_path = project_path.as_ref();
+    find_root(project_path).unwrap_or_else(|| project_path.to_path_buf())
+}
+
+/// Finds `name` in `node_modules/.bin` of the project or its workspace root.
+pub fn find_bin(project_path: impl AsRef<Path>, name: &str) -> Option<PathBuf> {
+    let project_path = project_path.as_ref();
+    let mut dirs = vec![project_path.to_path_buf()];
+    dirs.extend(find_root(project_path));
+    dirs.iter()
+        .map(|dir| dir.join("node_modules/.bin").join(name))
+        .find(|bin| bin.exists())
+}
+
+/// Working directory and arguments to run `script` for `project_path`.
+///
+/// Inside a workspace the script is run from the root with the package
+/// manager's workspace filter, so root-level `node_modules/.bin` is on `PATH`.
+pub fn script_command(
+    project_path: impl AsRef<Path>,
+    package_manager: &PackageManager,
+    script: &str,
+    extra: &[String],
+) -> (PathBuf, Vec<String>) {
+    let project_path = project_path.as_ref();
+    let member = find_root(project_path).and_then(|root| {
+        let workspace = Workspace::load(&root).ok()?;
+        let name = workspace.package_at(project_path)?.name.clone();
+        Some((root, name))
+    });
+
+    match member {
+        Some((root, name)) => {
+            let mut args: Vec<String> = match package_manager.kind {
+                PackageManagerKind::Pnpm => vec!["--filter".into(), name, "run".into()],
+                PackageManagerKind::Npm => vec!["run".into(), "-w".into(), name],
+                PackageManagerKind::YarnClassic | PackageManagerKind::YarnBerry => {
+                    vec!["workspace".into(), name, "run".into()]
+                }
+                PackageManagerKind::Bun => vec!["run".into(), "--filter".into(), name],
+            };
+            args.push(script.to_string());
+            if !extra.is_empty() {
+                if package_manager.kind == PackageManagerKind::Npm {
+                    args.push("--".to_string());
+                }
+                args.extend(extra.iter().cloned());
+            }
+            (root, args)
+        }
+        None => (
+            project_path.to_path_buf(),
+            package_manager.run_script_args(script, extra),
+        ),
+    }
+}
+
+/// Builds every AssemblyScript package in the workspace at `root`, dependencies
+/// first. Each package's output goes to a subdirectory of `config.output_dir`
+/// named after the package directory.
+pub fn build_all(
+    builder: &dyn WasmBuilder,
+    root: impl AsRef<Path>,
+    config: &BuildConfig,
+) -> PluginResult<Vec<(String, BuildResult)>> {
+    let root = root.as_ref();
+    let workspace = Workspace::load(root)?;
+    let asc_packages: Vec<&WorkspacePackage> = workspace
+        .packages
+        .iter()
+        .filter(|p| builder.can_handle_project(&p.path))
+        .collect();
+    if asc_packages.is_empty() {
+        return Err(PluginError::InvalidProjectStructure {
+            reason: format!(
+                "No AssemblyScript packages found in workspace {}",
+                root.display()
+            ),
+        });
+    }
+
+    let mut results = Vec::new();
+    for package in workspace.build_order(&asc_packages)? {
+        let output_dir = match package.path.file_name() {
+            Some(dir_name) => config.output_dir.join(dir_name),
+            None => config.output_dir.join(&package.name),
+        };
+        PathResolver::ensure_output_directory(&output_dir)?;
+
+        eprintln!("📦 Building workspace package {}...", package.name);
+        let package_config = BuildConfig {
+            project_path: package.path.clone(),
+            output_dir,
+            ..config.clone()
+        };
+        let result =
+            builder
+                .build(&package_config)
+                .map_err(|e| PluginError::WorkspacePackageFailed {
+                    package: package.name.clone(),
+                    source: Box::new(e),
+                })?;
+        results.push((package.name.clone(), result));
+    }
+
+    Ok(results)
+}
+
+fn member_patterns(dir: &Path) -> Option<Vec<String>> {
+    if let Ok(content) = fs::read_to_string(dir.join("pnpm-workspace.yaml")) {
+        return Some(parse_pnpm_workspace(&content));
+    }
+
+    let package = read_package_json(dir)?;
+    let workspaces = &package["workspaces"];
+    // Yarn also accepts `{ "packages": [...] }`.
+    let list = workspaces
+        .as_array()
+        .or_else(|| workspaces["packages"].as_array())?;
+    Some(
+        list.iter()
+            .filter_map(|p| p.as_str().map(str::to_string))
+            .collect(),
+    )
+}
+
+/// Reads the `packages:` list; the rest of the file is ignored.
+fn parse_pnpm_workspace(content: &str) -> Vec<String> {
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) && !trimmed.starts_with('-') {
+            in_packages = trimmed == "packages:";
+            continue;
+        }
+        if in_packages {
+            if let Some(item) = trimmed.strip_prefix('-') {
+                let item = item.split(" #").next().unwrap_or_default().trim();
+                patterns.push(item.trim_matches(['\'', '"']).to_string());
+            }
+        }
+    }
+    patterns
+}
+
+/// Expands a workspace pattern such as `packages/*`, `apps/**` or `tools/cli`.
+fn expand_pattern(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    let mut dirs = vec![root.to_path_buf()];
+
+    for segment in pattern.split('/') {
+        let mut next = Vec::new();
+        for dir in &dirs {
+            if segment == "**" {
+                collect_dirs(dir, &mut next);
+            } else if segment.contains('*') {
+                next.extend(subdirs(dir).into_iter().filter(|sub| {
+                    sub.file_name()
+                        .is_some_and(|n| matches_pattern(segment, &n.to_string_lossy()))
+                }));
+            } else {
+                let sub = dir.join(segment);
+                if sub.is_dir() {
+                    next.push(sub);
+                }
+            }
+        }
+        dirs = next;
+    }
+
+    dirs.into_iter()
+        .filter(|dir| dir.join("package.json").exists())
+        .map(|dir| canonical(&dir))
+        .collect()
+}
+
+fn collect_dirs(dir: &Path, out: &mut Vec<PathBuf>) {
+    out.push(dir.to_path_buf());
+    for sub in subdirs(dir) {
+        collect_dirs(&sub, out);
+    }
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .is_some_and(|n| n != "node_modules" && !n.to_string_lossy().starts_with('.'))
+        })
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn declared_dependencies(package: &serde_json::Value) -> Vec<String> {
+    ["dependencies", "devDependencies", "peerDependencies"]
+        .iter()
+        .filter_map(|section| package[section].as_object())
+        .flat_map(|deps| deps.keys().cloned())
+        .collect()
+}
+
+/// `dir`'s `package.json`, parsed; `None` when missing or not valid JSON.
+pub(crate) fn read_package_json(dir: impl AsRef<Path>) -> Option<serde_json::Value> {
+    let dir = dir.as_ref();
+    let content = fs::read_to_string(dir.join("package.json")).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{canonical, find_root, install_root, parse_pnpm_workspace, Workspace};
+    use std::fs;
+    use std::path::Path;
+
+    fn package(dir: &Path, json: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("package.json"), json).unwrap();
+    }
+
+    #[test]
+    fn npm_workspaces_array_marks_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        package(root, r#"{"workspaces": ["packages/*"]}"#);
+        package(&root.join("packages/a"), r#"{"name": "a"}"#);
+
+        let member = root.join("packages/a");
+        assert_eq!(find_root(&member), Some(canonical(root)));
+        assert_eq!(install_root(&member), canonical(root));
+        assert_eq!(find_root(root), Some(canonical(root)));
+    }
+
+    #[test]
+    fn yarn_workspaces_object_marks_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        package(
+            root,
+            r#"{"workspaces": {"packages": ["apps/*"], "nohoist": []}}"#,
+        );
+        package(&root.join("apps/web"), r#"{"name": "web"}"#);
+
+        assert_eq!(find_root(root.join("apps/web")), Some(canonical(root)));
+        let workspace = Workspace::load(root).unwrap();
+        assert_eq!(workspace.packages.len(), 1);
+        assert_eq!(workspace.packages[0].name, "web");
+    }
+
+    #[test]
+    fn pnpm_workspace_yaml_marks_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        package(root, r#"{"name": "monorepo"}"#);
+        fs::write(
+            root.join("pnpm-workspace.yaml"),
+            "packages:\n  - 'packages/*'\n  - \"!packages/skip\"\n",
+        )
+        .unwrap();
+        package(&root.join("packages/core"), r#"{"name": "core"}"#);
+        package(
+            &root.join("packages/app"),
+            r#"{"name": "app", "dependencies": {"core": "workspace:*"}}"#,
+        );
+        package(&root.join("packages/skip"), r#"{"name": "skip"}"#);
+
+        assert_eq!(find_root(root.join("packages/app")), Some(canonical(root)));
+        let workspace = Workspace::load(root).unwrap();
+        let names: Vec<_> = workspace.packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["app", "core"]);
+
+        let all: Vec<_> = workspace.packages.iter().collect();
+        let order: Vec<_> = workspace
+            .build_order(&all)
+            .unwrap()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(order, ["core", "app"]);
+    }
+
+    #[test]
+    fn standalone_project_is_its_own_install_root() {
+        let dir = tempfile::tempdir().unwrap();
+        package(
+            dir.path(),
+            r#"{"name": "solo", "workspaces": "not-a-list"}"#,
+        );
+        assert_eq!(find_root(dir.path()), None);
+        assert_eq!(install_root(dir.path()), dir.path());
+    }
+
+    #[test]
+    fn pnpm_workspace_reads_only_the_packages_list() {
+        let yaml = "\
+# monorepo
+packages:
+  - packages/*   # libraries
+  - 'apps/**'
+
+catalog:
+  - not-a-package
+";
+        assert_eq!(parse_pnpm_workspace(yaml), ["packages/*", "apps/**"]);
+    }
+}